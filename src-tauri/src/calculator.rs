@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+
+use log::debug;
+use regex::{Captures, Regex};
+
+use crate::{
+    expression,
+    math_detector::{MathCandidate, MathDetector},
+    model::state::EvalConfig,
+};
+
+/// The previous result of a player
+const ANS: &str = "ans";
+
+/// Source engine units are 0.75 inches
+const METERS_PER_HAMMER_UNIT: f64 = 0.01905;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Length,
+    Speed,
+    Time,
+    Frequency,
+    Currency,
+}
+
+/// Unit names, with how many meters, meters per second, seconds or hertz one of them is
+const UNITS: [(&str, Dimension, f64); 32] = [
+    ("hu", Dimension::Length, METERS_PER_HAMMER_UNIT),
+    ("u", Dimension::Length, METERS_PER_HAMMER_UNIT),
+    ("units", Dimension::Length, METERS_PER_HAMMER_UNIT),
+    ("m", Dimension::Length, 1.0),
+    ("cm", Dimension::Length, 0.01),
+    ("mm", Dimension::Length, 0.001),
+    ("km", Dimension::Length, 1000.0),
+    ("in", Dimension::Length, 0.0254),
+    ("inch", Dimension::Length, 0.0254),
+    ("ft", Dimension::Length, 0.3048),
+    ("feet", Dimension::Length, 0.3048),
+    ("yd", Dimension::Length, 0.9144),
+    ("mi", Dimension::Length, 1609.344),
+    // Minecraft blocks are a meter wide
+    ("blocks", Dimension::Length, 1.0),
+    ("hu/s", Dimension::Speed, METERS_PER_HAMMER_UNIT),
+    ("u/s", Dimension::Speed, METERS_PER_HAMMER_UNIT),
+    ("ups", Dimension::Speed, METERS_PER_HAMMER_UNIT),
+    ("m/s", Dimension::Speed, 1.0),
+    ("km/h", Dimension::Speed, 1.0 / 3.6),
+    ("kmh", Dimension::Speed, 1.0 / 3.6),
+    ("kph", Dimension::Speed, 1.0 / 3.6),
+    ("mph", Dimension::Speed, 0.44704),
+    ("ms", Dimension::Time, 0.001),
+    ("s", Dimension::Time, 1.0),
+    ("sec", Dimension::Time, 1.0),
+    ("min", Dimension::Time, 60.0),
+    ("h", Dimension::Time, 3600.0),
+    ("hr", Dimension::Time, 3600.0),
+    ("fps", Dimension::Frequency, 1.0),
+    ("hz", Dimension::Frequency, 1.0),
+    ("tick", Dimension::Frequency, 1.0),
+    ("tickrate", Dimension::Frequency, 1.0),
+];
+
+/// How a line asked to be evaluated
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Trigger {
+    /// Any line, answered if it is likely enough to be math
+    Detected,
+    /// `= 2 + 2`, always evaluated, with errors reported
+    Prefix,
+    /// `what is 2 + 2?`, answered whenever there is math in it
+    Question,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Radix {
+    Hex,
+    Bin,
+    Oct,
+    Int,
+}
+
+/// Evaluates math in chat, keeping variables and the last result of every player
+pub struct Calculator {
+    assignment: Regex,
+    format: Regex,
+    conversion: Regex,
+    detector: MathDetector,
+    /// Variables of each player, including `ans`
+    scopes: HashMap<String, HashMap<String, f64>>,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self {
+            assignment: Regex::new(r"^([a-zA-Z_][a-zA-Z0-9_]*)\s*=\s*(.+)$").unwrap(),
+            format: Regex::new(r"(?i)^(.+?)\s+(?:as|in|to)\s+(hex|bin|oct|int)$").unwrap(),
+            conversion: Regex::new(r"(?i)^(.+?)\s*([a-z/]+)\s+(?:to|in)\s+([a-z/]+)$").unwrap(),
+            detector: MathDetector::default(),
+            scopes: HashMap::new(),
+        }
+    }
+}
+
+impl Calculator {
+    /// Evaluates a chat line
+    ///
+    /// Besides plain expressions, lines can assign a variable (`hp = 100 - 27`), convert
+    /// units (`250 u/s to km/h`, `144 fps to ms`, `20 usd to eur`) or format the result
+    /// (`255 to hex`). Every result is remembered as `ans`. Numbers can be written in hex
+    /// (`0xff`) or binary (`0b101`).
+    ///
+    /// Lines starting with `=` are always evaluated and get the error back if they can't be.
+    /// Lines ending with `?` are answered if there is math in them. Other lines are only
+    /// answered when the detector is sure enough for the configured sensitivity, for
+    /// assignments, conversions and formats about the expression they evaluate, so chat
+    /// like `ping = 50` isn't answered.
+    ///
+    /// # Arguments
+    /// user - Who sent the line, variables are kept per player
+    /// line - The chat line
+    /// config - The precision, currency rates and sensitivity
+    ///
+    /// # Returns
+    /// The response, `None` if the line is not math
+    pub fn evaluate(&mut self, user: &str, line: &str, config: &EvalConfig) -> Option<String> {
+        let line = line.trim();
+        let (line, trigger) = if let Some(expression) = line.strip_prefix('=') {
+            (expression.trim(), Trigger::Prefix)
+        } else if let Some(expression) = line.strip_suffix('?') {
+            (expression.trim(), Trigger::Question)
+        } else {
+            (line, Trigger::Detected)
+        };
+
+        if trigger == Trigger::Detected && config.explicit_only {
+            return None;
+        }
+
+        let variables = self.scopes.entry(user.to_string()).or_default();
+        let detector = &self.detector;
+        let is_math = |expression: &str, variables: &HashMap<String, f64>| {
+            trigger != Trigger::Detected || is_sure(detector, expression, variables, config)
+        };
+
+        let (value, response) = if let Some(captures) = self.assignment.captures(line) {
+            let name = &captures[1];
+
+            if !is_math(&captures[2], variables) {
+                return None;
+            }
+
+            // `x` is only reserved for multiplying, but players can't be expected to know that
+            if name == ANS || expression::is_reserved(name) {
+                return Some(format!("Can't assign {}, the name is reserved", name));
+            }
+
+            let value = evaluate_expression(&captures[2], variables)?;
+            variables.insert(name.to_string(), value);
+
+            (
+                value,
+                format!("{} = {}", name, format_number(value, config.precision)?),
+            )
+        } else if let Some(captures) = self.format.captures(line) {
+            if !is_math(&captures[1], variables) {
+                return None;
+            }
+
+            let value = evaluate_expression(&captures[1], variables)?;
+            let radix = match captures[2].to_lowercase().as_str() {
+                "hex" => Radix::Hex,
+                "bin" => Radix::Bin,
+                "oct" => Radix::Oct,
+                _ => Radix::Int,
+            };
+
+            (value, format_radix(value, radix)?)
+        } else if let Some((value, unit)) = self
+            .conversion
+            .captures(line)
+            .filter(|captures| is_math(&captures[1], variables))
+            .and_then(|captures| convert_units(&captures, variables, config))
+        {
+            (
+                value,
+                format!("{} {}", format_number(value, config.precision)?, unit),
+            )
+        } else if trigger == Trigger::Prefix {
+            let value = match expression::parse(line).and_then(|expr| expr.evaluate(variables)) {
+                Ok(value) => value,
+                // Without digits it's more likely a smiley like =D than a typo
+                Err(error) if line.contains(|c: char| c.is_ascii_digit()) => {
+                    return Some(format!("Can't evaluate that: {}", error));
+                }
+                Err(_) => return None,
+            };
+
+            (value, format_number(value, config.precision)?)
+        } else {
+            let candidate = detector.detect(line, variables)?;
+
+            if trigger == Trigger::Detected && !is_sure_enough(&candidate, config) {
+                return None;
+            }
+
+            (
+                candidate.value,
+                format_number(candidate.value, config.precision)?,
+            )
+        };
+
+        variables.insert(ANS.to_string(), value);
+
+        Some(response)
+    }
+}
+
+/// Whether the detector is sure enough `expression` is math for the configured sensitivity
+fn is_sure(
+    detector: &MathDetector,
+    expression: &str,
+    variables: &HashMap<String, f64>,
+    config: &EvalConfig,
+) -> bool {
+    detector
+        .detect(expression, variables)
+        .is_some_and(|candidate| is_sure_enough(&candidate, config))
+}
+
+fn is_sure_enough(candidate: &MathCandidate, config: &EvalConfig) -> bool {
+    let sure = candidate.score + config.sensitivity > 100;
+
+    if !sure {
+        debug!(
+            "Not answering \"{}\", it scored {} as math",
+            candidate.expression, candidate.score
+        );
+    }
+
+    sure
+}
+
+/// Evaluates an expression the line already marked as math, e.g. the value of an assignment
+fn evaluate_expression(expression: &str, variables: &HashMap<String, f64>) -> Option<f64> {
+    expression::parse(expression)
+        .and_then(|expr| expr.evaluate(variables))
+        .map_err(|error| debug!("Not evaluating \"{}\": {}", expression, error))
+        .ok()
+}
+
+/// # Returns
+/// The converted value and the unit it is in, `None` if the units are unknown or don't match
+fn convert_units(
+    captures: &Captures,
+    variables: &HashMap<String, f64>,
+    config: &EvalConfig,
+) -> Option<(f64, String)> {
+    let from = find_unit(&captures[2], config)?;
+    let to = find_unit(&captures[3], config)?;
+    let value = evaluate_expression(&captures[1], variables)? * from.1;
+
+    let converted = match (from.0, to.0) {
+        (from, to) if from == to => value,
+        // Frame rate and frame time, e.g. 144 fps is 6.94 ms
+        (Dimension::Frequency, Dimension::Time) | (Dimension::Time, Dimension::Frequency) => {
+            1.0 / value
+        }
+        _ => return None,
+    };
+
+    Some((converted / to.1, captures[3].to_lowercase()))
+}
+
+fn find_unit(name: &str, config: &EvalConfig) -> Option<(Dimension, f64)> {
+    let name = name.to_lowercase();
+
+    if let Some((_, dimension, factor)) = UNITS.iter().find(|(unit, _, _)| *unit == name) {
+        return Some((*dimension, *factor));
+    }
+
+    // Rates are per US dollar
+    config
+        .currencies
+        .get(&name)
+        .filter(|rate| **rate > 0.0)
+        .map(|rate| (Dimension::Currency, 1.0 / rate))
+}
+
+/// Rounds to `precision` decimal places, without trailing zeros
+///
+/// # Returns
+/// `None` for results that are not a number, e.g. dividing by zero
+fn format_number(value: f64, precision: usize) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+
+    let mut text = format!("{:.*}", precision, value);
+
+    if text.contains('.') {
+        text = text.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+
+    if text == "-0" {
+        text = "0".to_string();
+    }
+
+    Some(text)
+}
+
+fn format_radix(value: f64, radix: Radix) -> Option<String> {
+    if !value.is_finite() || value.abs() >= i64::MAX as f64 {
+        return None;
+    }
+
+    let value = value.round() as i64;
+    let sign = if value < 0 { "-" } else { "" };
+    let digits = value.unsigned_abs();
+
+    Some(match radix {
+        Radix::Hex => format!("{}0x{:x}", sign, digits),
+        Radix::Bin => format!("{}0b{:b}", sign, digits),
+        Radix::Oct => format!("{}0o{:o}", sign, digits),
+        Radix::Int => value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(calculator: &mut Calculator, line: &str) -> Option<String> {
+        calculator.evaluate("Alyx", line, &EvalConfig::default())
+    }
+
+    #[test]
+    fn test_plain_expressions() {
+        let mut calculator = Calculator::default();
+
+        assert_eq!(evaluate(&mut calculator, "2 + 2"), Some("4".to_string()));
+        assert_eq!(
+            evaluate(&mut calculator, "10 / 3"),
+            Some("3.3333".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "3x4"), Some("12".to_string()));
+        assert_eq!(
+            evaluate(&mut calculator, "exp(0) + 1"),
+            Some("2".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "1 / 0"), None);
+        assert_eq!(evaluate(&mut calculator, "42"), None);
+        assert_eq!(evaluate(&mut calculator, "pi"), None);
+        assert_eq!(evaluate(&mut calculator, "nice shot"), None);
+
+        assert_eq!(
+            evaluate(&mut calculator, "what is 2 + 2"),
+            Some("4".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "max 2 players"), None);
+        assert_eq!(evaluate(&mut calculator, "we won 16-14"), None);
+        assert_eq!(evaluate(&mut calculator, "1.2.3"), None);
+        assert_eq!(
+            evaluate(&mut calculator, "max(2, 3) - 1"),
+            Some("2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_triggers() {
+        let mut calculator = Calculator::default();
+
+        // Marked lines are answered even when they look like a score
+        assert_eq!(evaluate(&mut calculator, "3-1"), None);
+        assert_eq!(evaluate(&mut calculator, "=3-1"), Some("2".to_string()));
+        assert_eq!(
+            evaluate(&mut calculator, "is it 3-1?"),
+            Some("2".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "= 42"), Some("42".to_string()));
+        assert_eq!(
+            evaluate(&mut calculator, "= 2 + armor"),
+            Some("Can't evaluate that: unknown variable armor at character 5".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "=D"), None);
+        assert_eq!(evaluate(&mut calculator, "anyone got 5 min?"), None);
+
+        let explicit_only = EvalConfig {
+            explicit_only: true,
+            ..Default::default()
+        };
+
+        assert_eq!(calculator.evaluate("Alyx", "2 + 2", &explicit_only), None);
+        assert_eq!(
+            calculator.evaluate("Alyx", "2 + 2?", &explicit_only),
+            Some("4".to_string())
+        );
+
+        let insensitive = EvalConfig {
+            sensitivity: 0,
+            ..Default::default()
+        };
+
+        assert_eq!(calculator.evaluate("Alyx", "2 + 2", &insensitive), None);
+    }
+
+    #[test]
+    fn test_variables_and_ans() {
+        let mut calculator = Calculator::default();
+
+        assert_eq!(
+            evaluate(&mut calculator, "= hp = 100 - 27"),
+            Some("hp = 73".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "hp / 2"),
+            Some("36.5".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "ans * 2"), Some("73".to_string()));
+
+        // Assignments in chat are answered like any other line, only when they're likely math
+        assert_eq!(evaluate(&mut calculator, "ping = 50"), None);
+        assert_eq!(evaluate(&mut calculator, "score = 16"), None);
+        assert_eq!(evaluate(&mut calculator, "kills = 3-1"), None);
+        assert_eq!(
+            evaluate(&mut calculator, "dmg = hp * 2 + 10"),
+            Some("dmg = 156".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "score = 16?"),
+            Some("score = 16".to_string())
+        );
+
+        // Reserved names can't be assigned, and other players have their own variables
+        assert_eq!(
+            evaluate(&mut calculator, "= x = 5"),
+            Some("Can't assign x, the name is reserved".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "pi = 2 * 1.5"),
+            Some("Can't assign pi, the name is reserved".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "x = 5"), None);
+        assert_eq!(evaluate(&mut calculator, "x * 2"), None);
+        assert_eq!(
+            calculator.evaluate("Barney", "hp * 2", &EvalConfig::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unit_conversions() {
+        let mut calculator = Calculator::default();
+
+        assert_eq!(
+            evaluate(&mut calculator, "= 250 u/s to km/h"),
+            Some("17.145 km/h".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "1000 hu in m?"),
+            Some("19.05 m".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "= 144 fps to ms"),
+            Some("6.9444 ms".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "= 10 usd to usd"),
+            Some("10 usd".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "5 m to s"), None);
+        assert_eq!(evaluate(&mut calculator, "go to mid"), None);
+
+        // Only marked or clearly math, "be there in 5 min to s" isn't asking
+        assert_eq!(evaluate(&mut calculator, "5 min to s"), None);
+        assert_eq!(
+            evaluate(&mut calculator, "60 * 5 s to min"),
+            Some("5 min".to_string())
+        );
+    }
+
+    #[test]
+    fn test_formats_and_precision() {
+        let mut calculator = Calculator::default();
+
+        assert_eq!(
+            evaluate(&mut calculator, "= 255 to hex"),
+            Some("0xff".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "0xff + 0b1 as bin"),
+            Some("0b100000000".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut calculator, "-10 / 4 to int?"),
+            Some("-3".to_string())
+        );
+        assert_eq!(evaluate(&mut calculator, "255 to hex"), None);
+
+        let config = EvalConfig {
+            precision: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            calculator.evaluate("Alyx", "2 / 3", &config),
+            Some("0.7".to_string())
+        );
+    }
+}
@@ -1,20 +1,44 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use chatgpt::types::CompletionResponse;
-use log::info;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
 use source_cmd_parser::{
-    log_parser::{ParseLog, SourceCmdFn},
+    log_parser::SourceCmdFn,
     model::{ChatMessage, ChatResponse},
 };
 use tokio::sync::Mutex;
+use uuid::Uuid;
 
 use crate::{
     error::SourceCmdGuiError,
-    lexer,
-    model::state::{AppState, CommandResponse},
+    identity,
+    model::{
+        state::{AppState, CommandResponse},
+        ModerationAction,
+    },
+    moderation::{self, ModerationHit},
+    output::Priority,
+    poll::Poll,
     python,
-    repository::ScriptRepository,
+    repository::{ModerationRepository, ScriptRepository, StatsRepository},
+    runtime, translation,
 };
+
+lazy_static! {
+    /// Ids of the commands players run, `track_stats` checks every chat line against them
+    static ref COMMAND_IDS: HashSet<String> = get_commands()
+        .into_iter()
+        .filter(|command| !command.global_command)
+        .map(|command| command.id)
+        .collect();
+}
+
 pub struct Command<
     T: Unpin + Clone + Send + Sync + 'static,
     E: std::error::Error + Send + Sync + 'static,
@@ -94,11 +118,62 @@ pub fn get_commands() -> Vec<Command<Arc<Mutex<AppState>>, SourceCmdGuiError>> {
             "Set the personality for ChatGPT".to_string(),
             false,
         ),
+        Command::new(
+            Box::new(translate),
+            "Translate".to_string(),
+            ".tr".to_string(),
+            "Translates text to a language, e.g. .tr de good game".to_string(),
+            false,
+        ),
+        Command::new(
+            Box::new(auto_translate),
+            "Auto Translate".to_string(),
+            "translate".to_string(),
+            "Translates messages from other players when auto-translate is turned on".to_string(),
+            true,
+        ),
         Command::new(
             Box::new(eval),
             "Eval".to_string(),
             "eval".to_string(),
-            "Evaluate a math expression".to_string(),
+            "Evaluates math in chat, or lines starting with = or ending with ?, with variables, ans, unit conversions and hex or binary results"
+                .to_string(),
+            true,
+        ),
+        Command::new(
+            Box::new(roll),
+            "Roll".to_string(),
+            ".roll".to_string(),
+            "Rolls dice, e.g. .roll 2d6+3 or .roll 4d6kh3 to keep the highest 3".to_string(),
+            false,
+        ),
+        Command::new(
+            Box::new(pick),
+            "Pick".to_string(),
+            ".pick".to_string(),
+            "Picks one of the options, e.g. .pick dust2 mirage inferno".to_string(),
+            false,
+        ),
+        Command::new(
+            Box::new(coin),
+            "Coin".to_string(),
+            ".coin".to_string(),
+            "Flips a coin".to_string(),
+            false,
+        ),
+        Command::new(
+            Box::new(poll),
+            "Poll".to_string(),
+            ".poll".to_string(),
+            "Starts a poll and posts the results when it ends, e.g. .poll Best map? dust2, mirage"
+                .to_string(),
+            false,
+        ),
+        Command::new(
+            Box::new(poll_vote),
+            "Poll Votes".to_string(),
+            "poll".to_string(),
+            "Counts messages naming an option of the running poll as votes".to_string(),
             true,
         ),
         Command::new(
@@ -122,6 +197,27 @@ pub fn get_commands() -> Vec<Command<Arc<Mutex<AppState>>, SourceCmdGuiError>> {
             "Mimics the message sent".to_string(),
             true,
         ),
+        Command::new(
+            Box::new(player_stats),
+            "Stats".to_string(),
+            ".stats".to_string(),
+            "Shows a player's stats, or your own without a name".to_string(),
+            false,
+        ),
+        Command::new(
+            Box::new(track_stats),
+            "Stats Tracker".to_string(),
+            "stats".to_string(),
+            "Records messages, commands, kills and deaths of every player".to_string(),
+            true,
+        ),
+        Command::new(
+            Box::new(moderate),
+            "Moderation".to_string(),
+            "moderation".to_string(),
+            "Filters banned words, spam and caps, and warns, kicks or mutes the sender".to_string(),
+            true,
+        ),
         Command::new(
             Box::new(handle_python_execution),
             "Python".to_string(),
@@ -208,6 +304,107 @@ pub async fn personality(
     Ok(None)
 }
 
+/// Translates the text after the language code
+///
+/// # Arguments
+/// chat_message - The chat message, `<lang> <text>`
+/// state - The app state
+///
+/// # Returns
+/// The translation, or how to use the command
+pub async fn translate(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command(&chat_message.command, &state).await {
+        return Ok(None);
+    }
+
+    let Some((target, text)) = chat_message.message.trim().split_once(char::is_whitespace) else {
+        return Ok(Some(ChatResponse::new(
+            "Usage: .tr <language code> <text>".to_string(),
+        )));
+    };
+
+    let translator = {
+        let state = state.lock().await;
+
+        translation::build(&state.config.translation, state.cmd_state.chat_gpt.clone())
+    };
+
+    let Some(translator) = translator else {
+        return Ok(None);
+    };
+
+    info!("Translate to {}: {}", target, text);
+
+    let translated = translator
+        .translate(text.trim(), &target.to_lowercase())
+        .await?;
+
+    Ok(Some(ChatResponse::new(translated)))
+}
+
+/// Translates messages from other players that are not in the target language
+///
+/// # Arguments
+/// chat_message - The chat message
+/// state - The app state
+///
+/// # Returns
+/// The translation prefixed with the detected language and the sender
+async fn auto_translate(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command("translate", &state).await {
+        return Ok(None);
+    }
+
+    let message = chat_message.raw_message;
+
+    if message.starts_with('.') {
+        return Ok(None);
+    }
+
+    let (config, translator) = {
+        let state = state.lock().await;
+        let config = state.config.translation.clone();
+
+        if !config.auto_translate
+            || identity::is_owner(
+                &chat_message.user_name,
+                &state.config,
+                &state.cmd_state.player_ids,
+            )
+        {
+            return Ok(None);
+        }
+
+        let translator = translation::build(&config, state.cmd_state.chat_gpt.clone());
+
+        (config, translator)
+    };
+
+    // Messages too short to tell the language of are left alone, most are "gg" and "lol"
+    let Some(language) = translation::detect_language(&message) else {
+        return Ok(None);
+    };
+
+    let Some(translator) = translator.filter(|_| language != config.target_language) else {
+        return Ok(None);
+    };
+
+    let translated = translator
+        .translate(&message, &config.target_language)
+        .await?;
+
+    Ok(Some(ChatResponse::new(format!(
+        "[{}] {}: {}",
+        language, chat_message.user_name, translated
+    ))))
+}
+
 pub async fn eval(
     chat_message: ChatMessage,
     state: Arc<Mutex<AppState>>,
@@ -218,32 +415,197 @@ pub async fn eval(
 
     let message = chat_message.raw_message;
 
-    if message.trim().parse::<f64>().is_ok() {
+    let response = {
+        let mut state = state.lock().await;
+        let config = state.config.eval.clone();
+
+        state
+            .cmd_state
+            .calculator
+            .evaluate(&chat_message.user_name, &message, &config)
+    };
+
+    Ok(response.map(|response| {
+        info!("Eval: {} = {}", message, response);
+
+        ChatResponse::new(response)
+    }))
+}
+
+/// Rolls dice notation
+///
+/// # Arguments
+/// chat_message - The chat message, e.g. `.roll 2d6+3`
+/// state - The app state
+///
+/// # Returns
+/// Every die rolled and the total, or what is wrong with the notation
+pub async fn roll(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command(&chat_message.command, &state).await {
         return Ok(None);
     }
 
-    let tokens = lexer::tokenize(message.as_str());
+    let roll = state
+        .lock()
+        .await
+        .cmd_state
+        .randomizer
+        .roll(&chat_message.message);
 
-    let expression = lexer::to_string(&tokens);
+    let response = match roll {
+        Ok(roll) => format!("{} rolled {}", chat_message.user_name, roll.detail),
+        Err(e) => e.to_string(),
+    };
 
-    if expression.is_empty()
-        || tokens.len() == 1
-        || tokens
-            .iter()
-            .all(|token| token.is_number() || token.is_parathesis())
-    {
+    Ok(Some(ChatResponse::new(response)))
+}
+
+pub async fn pick(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command(&chat_message.command, &state).await {
+        return Ok(None);
+    }
+
+    let picked = state
+        .lock()
+        .await
+        .cmd_state
+        .randomizer
+        .pick(&chat_message.message);
+
+    let response = match picked {
+        Some(picked) => format!("I pick {}", picked),
+        None => "Usage: .pick <option> <option>, or separate options with commas".to_string(),
+    };
+
+    Ok(Some(ChatResponse::new(response)))
+}
+
+pub async fn coin(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command(&chat_message.command, &state).await {
         return Ok(None);
     }
 
-    info!("Eval: {}", expression);
+    let side = state.lock().await.cmd_state.randomizer.flip_coin();
 
-    match meval::eval_str(expression.replace('x', "*")) {
-        Ok(response) => {
-            info!("Eval: {} = {}", message, response);
-            Ok(Some(ChatResponse::new(response.to_string())))
+    Ok(Some(ChatResponse::new(format!(
+        "{} flipped {}",
+        chat_message.user_name, side
+    ))))
+}
+
+/// Starts a poll that collects votes for the configured duration
+///
+/// # Arguments
+/// chat_message - The chat message, `<question>? <option>, <option>`
+/// state - The app state
+///
+/// # Returns
+/// How to vote, or the running poll if there already is one
+pub async fn poll(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command(&chat_message.command, &state).await {
+        return Ok(None);
+    }
+
+    let mut state_guard = state.lock().await;
+
+    if let Some(running) = &state_guard.cmd_state.poll {
+        return Ok(Some(ChatResponse::new(format!(
+            "A poll is already running: {}",
+            running.question
+        ))));
+    }
+
+    let Some(poll) = Poll::parse(&chat_message.message) else {
+        return Ok(Some(ChatResponse::new(
+            "Usage: .poll <question>? <option>, <option>, or just a question for yes or no"
+                .to_string(),
+        )));
+    };
+
+    let duration = state_guard.config.poll.duration;
+    let announcement = poll.announcement(duration);
+
+    info!(
+        "{} started a poll: {}",
+        chat_message.user_name, poll.question
+    );
+
+    let id = poll.id;
+    state_guard.cmd_state.poll = Some(poll);
+    state_guard.cmd_state.poll_timer = Some(tokio::spawn(close_poll(
+        state.clone(),
+        id,
+        Duration::from_secs(duration),
+    )));
+
+    Ok(Some(ChatResponse::new(announcement)))
+}
+
+/// Posts the results of the poll once its time is up, unless it was stopped or replaced
+///
+/// # Arguments
+/// state - The app state
+/// id - The poll to close
+/// duration - How long the poll runs
+async fn close_poll(state: Arc<Mutex<AppState>>, id: Uuid, duration: Duration) {
+    tokio::time::sleep(duration).await;
+
+    let mut state = state.lock().await;
+
+    let Some(poll) = Poll::close(&mut state.cmd_state.poll, id) else {
+        return;
+    };
+
+    state.cmd_state.poll_timer = None;
+
+    let results = poll.results();
+    let max_chat_length = state
+        .config
+        .parser
+        .get_max_chat_length(&state.config.custom_parser);
+
+    info!("{}", results);
+
+    state.outgoing.push(
+        runtime::limit_length(results, max_chat_length),
+        None,
+        Priority::Normal,
+    );
+}
+
+/// Counts messages naming an option of the running poll, by number or name, as votes
+async fn poll_vote(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command("poll", &state).await {
+        return Ok(None);
+    }
+
+    let mut state = state.lock().await;
+
+    if let Some(poll) = state.cmd_state.poll.as_mut() {
+        if poll.vote(&chat_message.user_name, &chat_message.raw_message) {
+            info!(
+                "{} voted {}",
+                chat_message.user_name, chat_message.raw_message
+            );
         }
-        Err(_) => Ok(None),
     }
+
+    Ok(None)
 }
 
 async fn chat_gpt_respond(
@@ -258,9 +620,13 @@ async fn chat_gpt_respond(
 
     let mut state = state.lock().await;
     let response_direction = state.config.response_direction.clone();
-    let user_name = &state.config.owner;
 
-    if chat_message.user_name.contains(user_name) || chat_message.message.starts_with('.') {
+    if identity::is_owner(
+        &chat_message.user_name,
+        &state.config,
+        &state.cmd_state.player_ids,
+    ) || chat_message.message.starts_with('.')
+    {
         return Ok(None);
     }
 
@@ -307,13 +673,17 @@ async fn mimic(
         return Ok(None);
     }
 
-    let owner = {
+    let is_owner = {
         let state = state.lock().await;
 
-        state.config.owner.clone()
+        identity::is_owner(
+            &chat_message.user_name,
+            &state.config,
+            &state.cmd_state.player_ids,
+        )
     };
 
-    if chat_message.user_name.contains(&owner) {
+    if is_owner {
         return Ok(None);
     }
 
@@ -322,6 +692,144 @@ async fn mimic(
     )))
 }
 
+async fn player_stats(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command(&chat_message.command, &state).await {
+        return Ok(None);
+    }
+
+    let name = match chat_message.message.trim() {
+        "" => chat_message.user_name.as_str(),
+        name => name,
+    };
+
+    let state = state.lock().await;
+
+    Ok(Some(ChatResponse::new(
+        match state.stats_repository.get_player(name) {
+            Some(stats) => stats.summary(),
+            None => format!("No stats for {}", name),
+        },
+    )))
+}
+
+async fn track_stats(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command("stats", &state).await {
+        return Ok(None);
+    }
+
+    let mut state = state.lock().await;
+
+    let is_command = COMMAND_IDS.contains(&chat_message.command)
+        || state
+            .script_repository
+            .get_script_by_trigger(&chat_message.command)
+            .await?
+            .is_some();
+
+    state
+        .stats_repository
+        .record_message(
+            &chat_message.user_name,
+            is_command.then_some(chat_message.command.as_str()),
+        )
+        .await?;
+
+    Ok(None)
+}
+
+/// Checks every message against the moderation filters and acts on the sender
+///
+/// # Arguments
+/// chat_message - The chat message
+/// state - The app state
+///
+/// # Returns
+/// A warning for the sender if that is the configured action, or the fallback for kicks and mutes
+async fn moderate(
+    chat_message: ChatMessage,
+    state: Arc<Mutex<AppState>>,
+) -> Result<Option<ChatResponse>, SourceCmdGuiError> {
+    if !can_run_command("moderation", &state).await {
+        return Ok(None);
+    }
+
+    let player = chat_message.user_name;
+    let message = chat_message.raw_message;
+
+    let (config, violation, rcon) = {
+        let mut state = state.lock().await;
+
+        if identity::is_owner(&player, &state.config, &state.cmd_state.player_ids) {
+            return Ok(None);
+        }
+
+        let config = state.config.moderation.clone();
+        let violation = state
+            .cmd_state
+            .moderator
+            .check(&config, &player, &message, Instant::now());
+
+        (config, violation, state.cmd_state.rcon.clone())
+    };
+
+    let Some(violation) = violation else {
+        return Ok(None);
+    };
+
+    let command = match config.action {
+        ModerationAction::Kick => Some(&config.kick_command),
+        ModerationAction::Mute => Some(&config.mute_command),
+        ModerationAction::Log | ModerationAction::Warn => None,
+    };
+
+    let action = match (command, rcon) {
+        (Some(command), Some(rcon)) => {
+            match rcon
+                .exec(&moderation::action_command(command, &player))
+                .await
+            {
+                Ok(_) => config.action,
+                Err(e) => {
+                    error!("Failed to moderate {} over RCON: {}", player, e);
+                    ModerationAction::Warn
+                }
+            }
+        }
+        (Some(_), None) => {
+            warn!(
+                "Kicking and muting need an RCON connection, warning {} instead",
+                player
+            );
+            ModerationAction::Warn
+        }
+        (None, _) => config.action,
+    };
+
+    info!("Moderation: {} ({}): {}", player, violation, message);
+
+    state
+        .lock()
+        .await
+        .moderation_repository
+        .record(ModerationHit {
+            time: Utc::now(),
+            player: player.clone(),
+            message,
+            reason: violation.to_string(),
+            action,
+        })
+        .await?;
+
+    Ok((action == ModerationAction::Warn)
+        .then(|| ChatResponse::new(moderation::warning(&config.warning, &player, &violation))))
+}
+
 /// Handles python execution
 ///
 /// # Arguments
@@ -343,7 +851,7 @@ async fn handle_python_execution(
     // Get the first word of the message
     let command = message.split_whitespace().next().unwrap_or_default();
 
-    let (script, config, python_context) = {
+    let (script, config, python_context, rcon) = {
         let state = state.lock().await;
 
         (
@@ -355,6 +863,7 @@ async fn handle_python_execution(
                 .flatten(),
             state.config.clone(),
             state.cmd_state.python_context.clone(),
+            state.cmd_state.rcon.clone(),
         )
     };
 
@@ -368,7 +877,8 @@ async fn handle_python_execution(
         chat_message.message = message.replace(command, "").trim().to_string();
 
         let (response, context) =
-            python::process_python_command(&script, chat_message, &config, python_context).await?;
+            python::process_python_command(&script, chat_message, &config, python_context, rcon)
+                .await?;
 
         {
             let mut state = state.lock().await;
@@ -383,43 +893,3 @@ async fn handle_python_execution(
         Ok(None)
     }
 }
-
-pub struct MinecraftParser {
-    regex: regex::Regex,
-}
-
-impl MinecraftParser {
-    pub fn new() -> Self {
-        Self {
-            regex: regex::Regex::new(r"\[CHAT\].*\] (.+?): (.*)").unwrap(),
-        }
-    }
-}
-
-impl Default for MinecraftParser {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl ParseLog for MinecraftParser {
-    fn parse_command(&self, line: &str) -> Option<ChatMessage> {
-        if let Some(captures) = self.regex.captures(line) {
-            let user_name = captures.get(1).unwrap().as_str().to_string();
-            let message = captures.get(2).unwrap().as_str().to_string();
-
-            let command = message.split_whitespace().next().unwrap().to_string();
-            let raw_message = message.clone();
-
-            let message = if message.starts_with(command.as_str()) {
-                message[command.len()..].trim().to_string()
-            } else {
-                message
-            };
-
-            Some(ChatMessage::new(user_name, message, command, raw_message))
-        } else {
-            None
-        }
-    }
-}
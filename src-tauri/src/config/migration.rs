@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    model::state::Config,
+};
+
+/// The config version written by this build.
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+/// A single step upgrading a config object from version `n` to `n + 1`.
+/// Values that must move out of the config file are added to the secrets map.
+type Migration = fn(&mut Map<String, Value>, &mut HashMap<String, String>) -> SourceCmdGuiResult;
+
+/// Migrations indexed by the version they upgrade from.
+const MIGRATIONS: [Migration; CURRENT_CONFIG_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+pub struct MigratedConfig {
+    pub config: Config,
+    /// Whether any migration ran, meaning the file on disk should be rewritten.
+    pub upgraded: bool,
+    /// Secrets extracted from the config, keyed by the name the config now refers to them by.
+    /// They must be stored before the upgraded config is written back.
+    pub secrets: HashMap<String, String>,
+}
+
+/// Upgrades a raw config json value to the current schema
+///
+/// # Arguments
+/// value - The config as read from disk
+///
+/// # Returns
+/// The deserialized config and whether it had to be upgraded
+pub fn migrate(value: Value) -> SourceCmdGuiResult<MigratedConfig> {
+    let Value::Object(mut object) = value else {
+        return Err(SourceCmdGuiError::InvalidConfigFile(
+            "expected a json object".to_string(),
+        ));
+    };
+
+    let mut version = read_version(&object)?;
+
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(SourceCmdGuiError::UnsupportedConfigVersion(
+            version,
+            CURRENT_CONFIG_VERSION,
+        ));
+    }
+
+    let upgraded = version < CURRENT_CONFIG_VERSION;
+    let mut secrets = HashMap::new();
+
+    while version < CURRENT_CONFIG_VERSION {
+        MIGRATIONS[version as usize](&mut object, &mut secrets)?;
+        version += 1;
+        object.insert("version".to_string(), Value::from(version));
+    }
+
+    let config = serde_json::from_value(Value::Object(object))?;
+
+    Ok(MigratedConfig {
+        config,
+        upgraded,
+        secrets,
+    })
+}
+
+fn read_version(object: &Map<String, Value>) -> SourceCmdGuiResult<u32> {
+    match object.get("version") {
+        // Configs written before versioning was introduced
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                SourceCmdGuiError::InvalidConfigFile(format!("invalid version {}", value))
+            }),
+    }
+}
+
+/// Unversioned configs. Older builds did not write `disabled_commands` or
+/// `response_direction`, and may have written `null` for them.
+fn v0_to_v1(
+    object: &mut Map<String, Value>,
+    _: &mut HashMap<String, String>,
+) -> SourceCmdGuiResult {
+    let defaults = Config::default();
+
+    for (key, default) in [
+        (
+            "disabled_commands",
+            serde_json::to_value(&defaults.disabled_commands)?,
+        ),
+        (
+            "response_direction",
+            Value::from(defaults.response_direction),
+        ),
+    ] {
+        if object.get(key).unwrap_or(&Value::Null).is_null() {
+            object.insert(key.to_string(), default);
+        }
+    }
+
+    Ok(())
+}
+
+/// The OpenAI API key used to be stored in plain text. It now lives in the
+/// secret store and the config only keeps its name.
+fn v1_to_v2(
+    object: &mut Map<String, Value>,
+    secrets: &mut HashMap<String, String>,
+) -> SourceCmdGuiResult {
+    let secret_name = Config::default().openai_api_key_secret;
+
+    if let Some(Value::String(api_key)) = object.remove("openai_api_key") {
+        if !api_key.is_empty() {
+            secrets.insert(secret_name.clone(), api_key);
+        }
+    }
+
+    object.insert(
+        "openai_api_key_secret".to_string(),
+        Value::from(secret_name),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::model::GameParser;
+
+    #[test]
+    fn test_migrate_unversioned_config() {
+        let value = json!({
+            "file_path": "/tmp/console.log",
+            "command_timeout": 5,
+            "owner": "owner",
+            "parser": "Minecraft",
+            "openai_api_key": "key",
+            "disabled_commands": ["mimic"],
+            "response_direction": "Be brief"
+        });
+
+        let migrated = migrate(value).unwrap();
+
+        assert!(migrated.upgraded);
+        assert_eq!(migrated.config.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(migrated.config.file_path, "/tmp/console.log");
+        assert_eq!(migrated.config.command_timeout, 5);
+        assert!(matches!(migrated.config.parser, GameParser::Minecraft));
+        assert_eq!(migrated.config.disabled_commands, vec!["mimic"]);
+        assert_eq!(migrated.config.response_direction, "Be brief");
+        assert_eq!(
+            migrated.secrets.get(&migrated.config.openai_api_key_secret),
+            Some(&"key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_migrate_config_without_command_settings() {
+        let value = json!({
+            "file_path": "/tmp/console.log",
+            "command_timeout": 10,
+            "owner": "owner",
+            "parser": "Counter Strike 2",
+            "openai_api_key": "",
+            "disabled_commands": null
+        });
+
+        let migrated = migrate(value).unwrap();
+
+        assert!(migrated.upgraded);
+        assert!(migrated.config.disabled_commands.is_empty());
+        assert_eq!(
+            migrated.config.response_direction,
+            Config::default().response_direction
+        );
+    }
+
+    #[test]
+    fn test_migrate_v1_config_moves_api_key_to_secrets() {
+        let value = json!({
+            "version": 1,
+            "file_path": "/tmp/console.log",
+            "command_timeout": 10,
+            "owner": "owner",
+            "parser": "Counter Strike 2",
+            "openai_api_key": "sk-test",
+            "disabled_commands": [],
+            "response_direction": "Be brief"
+        });
+
+        let migrated = migrate(value).unwrap();
+
+        assert!(migrated.upgraded);
+        assert_eq!(migrated.config.openai_api_key_secret, "openai_api_key");
+        assert_eq!(
+            migrated.secrets.get("openai_api_key"),
+            Some(&"sk-test".to_string())
+        );
+
+        let serialized = serde_json::to_string(&migrated.config).unwrap();
+        assert!(!serialized.contains("sk-test"));
+    }
+
+    #[test]
+    fn test_migrate_v1_config_without_api_key() {
+        let value = json!({ "version": 1, "openai_api_key": "" });
+
+        let migrated = migrate(value).unwrap();
+
+        assert!(migrated.secrets.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_current_config() {
+        let value = serde_json::to_value(Config::default()).unwrap();
+
+        let migrated = migrate(value).unwrap();
+
+        assert!(!migrated.upgraded);
+        assert_eq!(migrated.config.version, CURRENT_CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_fills_missing_fields() {
+        let value = json!({ "version": CURRENT_CONFIG_VERSION, "owner": "owner" });
+
+        let migrated = migrate(value).unwrap();
+
+        assert_eq!(migrated.config.owner, "owner");
+        assert_eq!(
+            migrated.config.command_timeout,
+            Config::default().command_timeout
+        );
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_version() {
+        let value = json!({ "version": CURRENT_CONFIG_VERSION + 1 });
+
+        assert!(matches!(
+            migrate(value),
+            Err(SourceCmdGuiError::UnsupportedConfigVersion(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_migrate_rejects_non_object() {
+        assert!(matches!(
+            migrate(json!([])),
+            Err(SourceCmdGuiError::InvalidConfigFile(_))
+        ));
+    }
+}
@@ -0,0 +1,7 @@
+mod migration;
+mod overrides;
+mod validation;
+
+pub use migration::{migrate, MigratedConfig, CURRENT_CONFIG_VERSION};
+pub use overrides::{resolve_config_dir, ConfigOverrides};
+pub use validation::{format_field_errors, validate, ConfigFieldError};
@@ -0,0 +1,358 @@
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use crate::{
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    model::state::Config,
+};
+
+/// Prefix of the environment variables overriding config fields,
+/// e.g. `SOURCE_CMD_GUI_OWNER` or `SOURCE_CMD_GUI_COMMAND_TIMEOUT`.
+pub const ENV_PREFIX: &str = "SOURCE_CMD_GUI_";
+
+/// Environment variable pointing at the config directory
+pub const HOME_ENV: &str = "SOURCE_CMD_GUI_HOME";
+
+/// Command line flag pointing at the config directory
+const HOME_ARG: &str = "home";
+
+/// Resolves the config directory, preferring `--home <dir>` over `SOURCE_CMD_GUI_HOME`
+///
+/// # Arguments
+/// args - The command line arguments, including the program name
+/// vars - Lookup for environment variables
+///
+/// # Returns
+/// The directory, or `None` when the default under the home directory should be used
+pub fn resolve_config_dir(
+    args: &[String],
+    vars: impl Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    parse_args(args)
+        .into_iter()
+        .rev()
+        .find(|(name, _)| name == HOME_ARG)
+        .map(|(_, value)| value)
+        .or_else(|| vars(HOME_ENV))
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Config values set outside the config file.
+///
+/// Resolution order is defaults → file → environment → command line, so
+/// these are applied on top of whatever was loaded from `config.json`.
+#[derive(Default, Clone)]
+pub struct ConfigOverrides {
+    // In application order, later values win
+    values: Vec<(String, String)>,
+}
+
+impl ConfigOverrides {
+    /// Collects `SOURCE_CMD_GUI_<FIELD>` variables followed by `--<field> <value>` arguments.
+    /// Every config field can be overridden; nested fields are joined with dots on the
+    /// command line and underscores in the environment, e.g. `--section.field` or
+    /// `SOURCE_CMD_GUI_SECTION_FIELD`. Field names on the command line may use dashes,
+    /// e.g. `--command-timeout 5`. List fields like `disabled_commands` take a comma separated list.
+    pub fn new(args: &[String], vars: impl Fn(&str) -> Option<String>) -> Self {
+        let fields = overridable_fields();
+        let mut values = Vec::new();
+
+        for field in &fields {
+            if let Some(value) = vars(&env_name(field)) {
+                values.push((field.clone(), value));
+            }
+        }
+
+        for (name, value) in parse_args(args) {
+            if let Some(field) = fields
+                .iter()
+                .find(|field| **field == name || field.replace('.', "_") == name)
+            {
+                values.push((field.clone(), value));
+            }
+        }
+
+        Self { values }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The overridden field names, nested ones joined with dots
+    pub fn fields(&self) -> Vec<String> {
+        let mut fields: Vec<_> = self.values.iter().map(|(field, _)| field.clone()).collect();
+        fields.sort_unstable();
+        fields.dedup();
+
+        fields
+    }
+
+    pub fn apply(&self, config: &mut Config) -> SourceCmdGuiResult {
+        for (field, raw) in &self.values {
+            let mut object = serde_json::to_value(&*config)?;
+
+            if let Some(value) = object.pointer_mut(&pointer(field)) {
+                *value = parse_value(field, value, raw)?;
+            }
+
+            *config = serde_json::from_value(object).map_err(|e| {
+                SourceCmdGuiError::InvalidConfigOverride(field.clone(), e.to_string())
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces overridden fields in `config` with the values from `persisted`,
+    /// so saving from the UI never writes an override into the config file.
+    pub fn restore(&self, config: &mut Config, persisted: &Config) -> SourceCmdGuiResult {
+        let persisted = serde_json::to_value(persisted)?;
+        let mut object = serde_json::to_value(&*config)?;
+
+        for field in self.fields() {
+            let pointer = pointer(&field);
+
+            if let (Some(value), Some(persisted)) =
+                (object.pointer_mut(&pointer), persisted.pointer(&pointer))
+            {
+                *value = persisted.clone();
+            }
+        }
+
+        *config = serde_json::from_value(object)?;
+
+        Ok(())
+    }
+}
+
+/// Every leaf of the serialized default config except `version`, nested fields joined with dots
+fn overridable_fields() -> Vec<String> {
+    fn collect(value: &Value, path: String, fields: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+
+                    collect(value, path, fields);
+                }
+            }
+            _ => fields.push(path),
+        }
+    }
+
+    let mut fields = Vec::new();
+
+    if let Ok(config) = serde_json::to_value(Config::default()) {
+        collect(&config, String::new(), &mut fields);
+    }
+
+    fields.retain(|field| field != "version");
+
+    fields
+}
+
+fn env_name(field: &str) -> String {
+    format!("{}{}", ENV_PREFIX, field.replace('.', "_").to_uppercase())
+}
+
+fn pointer(field: &str) -> String {
+    format!("/{}", field.replace('.', "/"))
+}
+
+/// Parses `raw` as the same kind of value the field currently holds
+fn parse_value(field: &str, current: &Value, raw: &str) -> SourceCmdGuiResult<Value> {
+    let invalid =
+        |reason: String| SourceCmdGuiError::InvalidConfigOverride(field.to_string(), reason);
+
+    Ok(match current {
+        Value::Number(_) => match serde_json::from_str(raw.trim()) {
+            Ok(Value::Number(number)) => Value::Number(number),
+            _ => return Err(invalid(format!("expected a number, got {:?}", raw))),
+        },
+        Value::Bool(_) => Value::from(
+            raw.trim()
+                .parse::<bool>()
+                .map_err(|e| invalid(e.to_string()))?,
+        ),
+        Value::Array(_) => Value::from(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>(),
+        ),
+        _ => Value::from(raw),
+    })
+}
+
+/// Parses `--name value` and `--name=value` pairs, normalizing dashes in names to underscores.
+/// Anything else (the program name, flags meant for Tauri) is skipped.
+fn parse_args(args: &[String]) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut args = args.iter().skip(1).peekable();
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            continue;
+        };
+
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, value.to_string()),
+            None => match args.next_if(|value| !value.starts_with("--")) {
+                Some(value) => (flag, value.clone()),
+                None => continue,
+            },
+        };
+
+        pairs.push((name.replace('-', "_"), value));
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::model::GameParser;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        std::iter::once("source-cmd-gui")
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_resolve_config_dir() {
+        assert_eq!(resolve_config_dir(&args(&[]), vars(&[])), None);
+        assert_eq!(
+            resolve_config_dir(&args(&[]), vars(&[(HOME_ENV, "/tmp/env")])),
+            Some(PathBuf::from("/tmp/env"))
+        );
+        assert_eq!(
+            resolve_config_dir(
+                &args(&["--home", "/tmp/cli"]),
+                vars(&[(HOME_ENV, "/tmp/env")])
+            ),
+            Some(PathBuf::from("/tmp/cli"))
+        );
+    }
+
+    #[test]
+    fn test_cli_overrides_env() {
+        let overrides = ConfigOverrides::new(
+            &args(&["--owner=cli", "--command-timeout", "3"]),
+            vars(&[
+                ("SOURCE_CMD_GUI_OWNER", "env"),
+                ("SOURCE_CMD_GUI_PARSER", "Minecraft"),
+                ("SOURCE_CMD_GUI_DISABLED_COMMANDS", "mimic, chatgpt"),
+            ]),
+        );
+        let mut config = Config::default();
+
+        overrides.apply(&mut config).unwrap();
+
+        assert_eq!(config.owner, "cli");
+        assert_eq!(config.command_timeout, 3);
+        assert!(matches!(config.parser, GameParser::Minecraft));
+        assert_eq!(config.disabled_commands, vec!["mimic", "chatgpt"]);
+        assert_eq!(
+            overrides.fields(),
+            vec!["command_timeout", "disabled_commands", "owner", "parser"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_override() {
+        let overrides =
+            ConfigOverrides::new(&args(&[]), vars(&[("SOURCE_CMD_GUI_PARSER", "Quake")]));
+
+        assert!(matches!(
+            overrides.apply(&mut Config::default()),
+            Err(SourceCmdGuiError::InvalidConfigOverride(field, _)) if field == "parser"
+        ));
+    }
+
+    #[test]
+    fn test_restore_keeps_persisted_values() {
+        let overrides = ConfigOverrides::new(&args(&["--owner", "cli"]), vars(&[]));
+
+        let persisted = Config {
+            owner: "file".to_string(),
+            ..Default::default()
+        };
+
+        let mut config = persisted.clone();
+        overrides.apply(&mut config).unwrap();
+        config.response_direction = "Edited in the UI".to_string();
+
+        overrides.restore(&mut config, &persisted).unwrap();
+
+        assert_eq!(config.owner, "file");
+        assert_eq!(config.response_direction, "Edited in the UI");
+    }
+
+    #[test]
+    fn test_nested_overrides() {
+        let overrides = ConfigOverrides::new(
+            &args(&[
+                "--custom-parser.max-chat-length",
+                "100",
+                "--auto-restart=true",
+            ]),
+            vars(&[("SOURCE_CMD_GUI_CUSTOM_PARSER_CHAT_KEY", "u")]),
+        );
+        let persisted = Config::default();
+        let mut config = persisted.clone();
+
+        overrides.apply(&mut config).unwrap();
+
+        assert_eq!(config.custom_parser.chat_key, "u");
+        assert_eq!(config.custom_parser.max_chat_length, 100);
+        assert!(config.auto_restart);
+        assert_eq!(
+            overrides.fields(),
+            vec![
+                "auto_restart",
+                "custom_parser.chat_key",
+                "custom_parser.max_chat_length"
+            ]
+        );
+
+        overrides.restore(&mut config, &persisted).unwrap();
+
+        assert_eq!(config.custom_parser.chat_key, "y");
+        assert_eq!(config.custom_parser.max_chat_length, 127);
+    }
+
+    #[test]
+    fn test_invalid_nested_override() {
+        let overrides = ConfigOverrides::new(
+            &args(&["--custom-parser-max-chat-length", "lots"]),
+            vars(&[]),
+        );
+
+        assert!(matches!(
+            overrides.apply(&mut Config::default()),
+            Err(SourceCmdGuiError::InvalidConfigOverride(field, _))
+                if field == "custom_parser.max_chat_length"
+        ));
+    }
+}
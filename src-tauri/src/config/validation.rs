@@ -0,0 +1,564 @@
+use std::{fmt, fs::File, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    identity::account_id,
+    model::{
+        state::{
+            Config, CustomParserConfig, EvalConfig, ModerationConfig, OutputConfig,
+            TranslationConfig,
+        },
+        GameParser, ModerationAction, OutputKind, TranslationBackend,
+    },
+    output::parse_key,
+    parsers::CustomParser,
+    translation,
+};
+
+/// A problem with a single config field, phrased so the user knows what to change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigFieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Joins field errors for `SourceCmdGuiError::InvalidConfig`
+pub fn format_field_errors(errors: &[ConfigFieldError]) -> String {
+    errors
+        .iter()
+        .map(ConfigFieldError::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Checks a config before the parser is started
+///
+/// # Arguments
+/// config - The config to check
+/// command_ids - The ids of every built-in command
+/// api_key - The OpenAI API key referenced by the config, if one is stored
+/// rcon_password - The RCON password referenced by the config, if one is stored
+///
+/// # Returns
+/// Every problem found, empty when the config is usable
+pub fn validate(
+    config: &Config,
+    command_ids: &[String],
+    api_key: Option<&str>,
+    rcon_password: Option<&str>,
+) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+
+    if let Some(error) = validate_file_path(&config.file_path) {
+        errors.push(error);
+    }
+
+    if config.parser == GameParser::Custom {
+        errors.extend(validate_custom_parser(&config.custom_parser));
+    }
+
+    errors.extend(validate_output(&config.output, rcon_password));
+
+    if config.output.kind == OutputKind::MinecraftRcon && config.parser != GameParser::Minecraft {
+        errors.push(ConfigFieldError::new(
+            "output.kind",
+            "Minecraft RCON only works with the Minecraft parser",
+        ));
+    }
+
+    errors.extend(validate_moderation(&config.moderation, rcon_password));
+    errors.extend(validate_translation(&config.translation, api_key));
+    errors.extend(validate_eval(&config.eval));
+
+    if config.command_timeout == 0 {
+        errors.push(ConfigFieldError::new(
+            "command_timeout",
+            "Must be at least 1 second",
+        ));
+    }
+
+    if !(10..=3600).contains(&config.poll.duration) {
+        errors.push(ConfigFieldError::new(
+            "poll.duration",
+            "Polls can run from 10 seconds to an hour",
+        ));
+    }
+
+    if config.owner.trim().is_empty() {
+        errors.push(ConfigFieldError::new(
+            "owner",
+            "Enter your in-game name so the bot can ignore your own messages",
+        ));
+    }
+
+    if !config.owner_steam_id.trim().is_empty() && account_id(&config.owner_steam_id).is_none() {
+        errors.push(ConfigFieldError::new(
+            "owner_steam_id",
+            "Enter a Steam ID like STEAM_0:1:1234, [U:1:2469] or 76561197960268197, or leave it empty",
+        ));
+    }
+
+    let unknown_commands: Vec<&str> = config
+        .disabled_commands
+        .iter()
+        .filter(|&id| !command_ids.contains(id))
+        .map(String::as_str)
+        .collect();
+
+    if !unknown_commands.is_empty() {
+        errors.push(ConfigFieldError::new(
+            "disabled_commands",
+            format!("Unknown command ids: {}", unknown_commands.join(", ")),
+        ));
+    }
+
+    if let Some(api_key) = api_key {
+        if !api_key.starts_with("sk-") || api_key.contains(char::is_whitespace) {
+            errors.push(ConfigFieldError::new(
+                "openai_api_key_secret",
+                "The stored OpenAI API key is not valid, OpenAI keys start with \"sk-\"",
+            ));
+        }
+    }
+
+    errors
+}
+
+fn validate_file_path(file_path: &str) -> Option<ConfigFieldError> {
+    let error = |message: String| Some(ConfigFieldError::new("file_path", message));
+
+    if file_path.trim().is_empty() {
+        return error("Choose the game's console log file".to_string());
+    }
+
+    let path = Path::new(file_path);
+
+    if !path.exists() {
+        return error(format!(
+            "{} does not exist, make sure the game is logging to it (e.g. launch with -condebug)",
+            file_path
+        ));
+    }
+
+    if path.is_dir() {
+        return error(format!("{} is a directory, not a log file", file_path));
+    }
+
+    if let Err(e) = File::open(path) {
+        return error(format!("{} cannot be read: {}", file_path, e));
+    }
+
+    None
+}
+
+fn validate_custom_parser(custom: &CustomParserConfig) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+
+    if let Err(e) = CustomParser::new(&custom.pattern) {
+        errors.push(ConfigFieldError::new(
+            "custom_parser.pattern",
+            e.to_string(),
+        ));
+    }
+
+    if custom.chat_key.chars().count() != 1 {
+        errors.push(ConfigFieldError::new(
+            "custom_parser.chat_key",
+            "Enter the single key that opens the chat",
+        ));
+    }
+
+    if custom.max_chat_length == 0 {
+        errors.push(ConfigFieldError::new(
+            "custom_parser.max_chat_length",
+            "Must be at least 1 character",
+        ));
+    }
+
+    errors
+}
+
+fn validate_output(output: &OutputConfig, rcon_password: Option<&str>) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+
+    match output.kind {
+        OutputKind::ExecCfg => {
+            let path = Path::new(&output.exec_cfg_path);
+
+            if path.extension().and_then(|extension| extension.to_str()) != Some("cfg") {
+                errors.push(ConfigFieldError::new(
+                    "output.exec_cfg_path",
+                    "Choose a .cfg file in the game's cfg folder",
+                ));
+            } else if !path.parent().is_some_and(Path::is_dir) {
+                errors.push(ConfigFieldError::new(
+                    "output.exec_cfg_path",
+                    format!("The folder of {} does not exist", output.exec_cfg_path),
+                ));
+            }
+
+            if !output.exec_key.trim().is_empty() && parse_key(&output.exec_key).is_none() {
+                errors.push(ConfigFieldError::new(
+                    "output.exec_key",
+                    "Enter a single key or F1 to F12, or leave it empty to press it yourself",
+                ));
+            }
+        }
+        OutputKind::Rcon | OutputKind::MinecraftRcon => {
+            let valid_address = output
+                .rcon_address
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+
+            if !valid_address {
+                errors.push(ConfigFieldError::new(
+                    "output.rcon_address",
+                    "Enter the server address as host:port, e.g. 127.0.0.1:27015",
+                ));
+            }
+
+            if rcon_password.unwrap_or_default().is_empty() {
+                errors.push(ConfigFieldError::new(
+                    "output.rcon_password_secret",
+                    "Store the server's RCON password",
+                ));
+            }
+        }
+        OutputKind::Keystrokes | OutputKind::Capture => {}
+    }
+
+    errors
+}
+
+fn validate_moderation(
+    moderation: &ModerationConfig,
+    rcon_password: Option<&str>,
+) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+
+    if moderation.spam_window == 0 && (moderation.max_messages > 0 || moderation.max_repeats > 0) {
+        errors.push(ConfigFieldError::new(
+            "moderation.spam_window",
+            "Must be at least 1 second for the spam checks",
+        ));
+    }
+
+    if moderation.max_caps_percent > 100 {
+        errors.push(ConfigFieldError::new(
+            "moderation.max_caps_percent",
+            "Enter a percentage up to 100, or 0 to allow any caps",
+        ));
+    }
+
+    let (field, command) = match moderation.action {
+        ModerationAction::Kick => ("moderation.kick_command", &moderation.kick_command),
+        ModerationAction::Mute => ("moderation.mute_command", &moderation.mute_command),
+        ModerationAction::Log | ModerationAction::Warn => return errors,
+    };
+
+    if !command.contains("{player}") {
+        errors.push(ConfigFieldError::new(
+            field,
+            "The command needs a {player} placeholder for the player's name",
+        ));
+    }
+
+    if rcon_password.unwrap_or_default().is_empty() {
+        errors.push(ConfigFieldError::new(
+            "moderation.action",
+            "Kicking and muting run over RCON, store the server's RCON password in the output settings",
+        ));
+    }
+
+    errors
+}
+
+fn validate_translation(
+    translation: &TranslationConfig,
+    api_key: Option<&str>,
+) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+
+    match translation.backend {
+        TranslationBackend::Http => {
+            if !translation.endpoint.starts_with("http://")
+                && !translation.endpoint.starts_with("https://")
+            {
+                errors.push(ConfigFieldError::new(
+                    "translation.endpoint",
+                    "Enter the translation URL, e.g. http://localhost:5000/translate",
+                ));
+            }
+        }
+        TranslationBackend::ChatGpt => {
+            if translation.auto_translate && api_key.unwrap_or_default().is_empty() {
+                errors.push(ConfigFieldError::new(
+                    "translation.backend",
+                    "Translating with ChatGPT needs an OpenAI API key",
+                ));
+            }
+        }
+    }
+
+    if translation.auto_translate && !translation::is_language_code(&translation.target_language) {
+        errors.push(ConfigFieldError::new(
+            "translation.target_language",
+            "Enter a two letter language code like en, de or es",
+        ));
+    }
+
+    errors
+}
+
+fn validate_eval(eval: &EvalConfig) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+
+    if eval.precision > 15 {
+        errors.push(ConfigFieldError::new(
+            "eval.precision",
+            "Results can be rounded to at most 15 decimal places",
+        ));
+    }
+
+    if eval.sensitivity > 100 {
+        errors.push(ConfigFieldError::new(
+            "eval.sensitivity",
+            "Enter a sensitivity up to 100, or 0 to only answer lines marked with = or ?",
+        ));
+    }
+
+    let mut invalid: Vec<&str> = eval
+        .currencies
+        .iter()
+        .filter(|(code, rate)| {
+            code.is_empty()
+                || !code.chars().all(|c| c.is_ascii_lowercase())
+                || !rate.is_finite()
+                || **rate <= 0.0
+        })
+        .map(|(code, _)| code.as_str())
+        .collect();
+
+    if !invalid.is_empty() {
+        invalid.sort();
+
+        errors.push(ConfigFieldError::new(
+            "eval.currencies",
+            format!(
+                "Currencies need a lowercase code and a rate above 0: {}",
+                invalid.join(", ")
+            ),
+        ));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::state::PollConfig;
+
+    use super::*;
+
+    fn command_ids() -> Vec<String> {
+        vec![".ping".to_string(), "mimic".to_string()]
+    }
+
+    fn fields(errors: &[ConfigFieldError]) -> Vec<&str> {
+        errors.iter().map(|error| error.field.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate_valid_config() {
+        let log = std::env::temp_dir().join(format!("{}.log", uuid::Uuid::new_v4()));
+        std::fs::write(&log, "").unwrap();
+
+        let config = Config {
+            file_path: log.to_string_lossy().to_string(),
+            owner: "owner".to_string(),
+            disabled_commands: vec!["mimic".to_string()],
+            ..Default::default()
+        };
+
+        assert!(validate(&config, &command_ids(), Some("sk-test"), None).is_empty());
+
+        std::fs::remove_file(log).unwrap();
+    }
+
+    #[test]
+    fn test_validate_reports_every_field() {
+        let config = Config {
+            file_path: String::new(),
+            command_timeout: 0,
+            poll: PollConfig { duration: 5 },
+            owner: " ".to_string(),
+            owner_steam_id: "STEAM_0".to_string(),
+            disabled_commands: vec!["mimic".to_string(), ".missing".to_string()],
+            ..Default::default()
+        };
+
+        let errors = validate(&config, &command_ids(), Some("not a key"), None);
+
+        assert_eq!(
+            fields(&errors),
+            vec![
+                "file_path",
+                "command_timeout",
+                "poll.duration",
+                "owner",
+                "owner_steam_id",
+                "disabled_commands",
+                "openai_api_key_secret"
+            ]
+        );
+        assert!(errors[5].message.contains(".missing"));
+        assert!(!errors[5].message.contains("mimic"));
+    }
+
+    #[test]
+    fn test_validate_missing_and_directory_paths() {
+        let missing = validate_file_path("/nonexistent/console.log").unwrap();
+        assert!(missing.message.contains("does not exist"));
+
+        let directory = validate_file_path(&std::env::temp_dir().to_string_lossy()).unwrap();
+        assert!(directory.message.contains("is a directory"));
+    }
+
+    #[test]
+    fn test_validate_custom_parser() {
+        let custom = CustomParserConfig {
+            pattern: "(?P<user>.+): (?P<text>.+)".to_string(),
+            chat_key: "enter".to_string(),
+            max_chat_length: 0,
+        };
+
+        assert_eq!(
+            fields(&validate_custom_parser(&custom)),
+            vec![
+                "custom_parser.pattern",
+                "custom_parser.chat_key",
+                "custom_parser.max_chat_length"
+            ]
+        );
+        assert!(validate_custom_parser(&CustomParserConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_output() {
+        let exec_cfg = OutputConfig {
+            kind: OutputKind::ExecCfg,
+            exec_cfg_path: "/nonexistent/cfg/source_cmd.cfg".to_string(),
+            exec_key: "enter".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fields(&validate_output(&exec_cfg, None)),
+            vec!["output.exec_cfg_path", "output.exec_key"]
+        );
+
+        let rcon = OutputConfig {
+            kind: OutputKind::Rcon,
+            rcon_address: "localhost".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fields(&validate_output(&rcon, Some(""))),
+            vec!["output.rcon_address", "output.rcon_password_secret"]
+        );
+        assert!(validate_output(&OutputConfig::default(), None).is_empty());
+    }
+
+    #[test]
+    fn test_validate_moderation() {
+        let kick = ModerationConfig {
+            spam_window: 0,
+            max_caps_percent: 150,
+            action: ModerationAction::Kick,
+            kick_command: "kick".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fields(&validate_moderation(&kick, None)),
+            vec![
+                "moderation.spam_window",
+                "moderation.max_caps_percent",
+                "moderation.kick_command",
+                "moderation.action"
+            ]
+        );
+
+        let mute = ModerationConfig {
+            action: ModerationAction::Mute,
+            ..Default::default()
+        };
+
+        assert!(validate_moderation(&mute, Some("secret")).is_empty());
+        assert!(validate_moderation(&ModerationConfig::default(), None).is_empty());
+    }
+
+    #[test]
+    fn test_validate_translation() {
+        let http = TranslationConfig {
+            backend: TranslationBackend::Http,
+            endpoint: "localhost:5000".to_string(),
+            auto_translate: true,
+            target_language: "english".to_string(),
+        };
+
+        assert_eq!(
+            fields(&validate_translation(&http, None)),
+            vec!["translation.endpoint", "translation.target_language"]
+        );
+
+        let chat_gpt = TranslationConfig {
+            auto_translate: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fields(&validate_translation(&chat_gpt, None)),
+            vec!["translation.backend"]
+        );
+        assert!(validate_translation(&chat_gpt, Some("sk-test")).is_empty());
+        assert!(validate_translation(&TranslationConfig::default(), None).is_empty());
+    }
+
+    #[test]
+    fn test_validate_eval() {
+        let mut eval = EvalConfig {
+            precision: 16,
+            sensitivity: 101,
+            ..Default::default()
+        };
+        eval.currencies.insert("EUR".to_string(), 0.9);
+        eval.currencies.insert("btc".to_string(), 0.0);
+
+        let errors = validate_eval(&eval);
+
+        assert_eq!(
+            fields(&errors),
+            vec!["eval.precision", "eval.sensitivity", "eval.currencies"]
+        );
+        assert!(errors[2].message.ends_with("EUR, btc"));
+        assert!(validate_eval(&EvalConfig::default()).is_empty());
+    }
+}
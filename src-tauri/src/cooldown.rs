@@ -0,0 +1,56 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Makes players wait between commands, the parser's own timeout can't change while it runs
+#[derive(Default)]
+pub struct CommandCooldown {
+    last_command: Mutex<HashMap<String, Instant>>,
+}
+
+impl CommandCooldown {
+    /// Records a command run by a player
+    ///
+    /// # Arguments
+    /// player - Who ran the command
+    /// timeout - How long players wait between commands, read from the live config
+    /// now - When the command was run
+    ///
+    /// # Returns
+    /// Whether the command may run, `false` while the player is waiting
+    pub fn allow(&self, player: &str, timeout: Duration, now: Instant) -> bool {
+        let mut last_command = self.last_command.lock().unwrap();
+
+        if let Some(last) = last_command.get(player) {
+            if now.duration_since(*last) < timeout {
+                return false;
+            }
+        }
+
+        last_command.insert(player.to_string(), now);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_uses_the_current_timeout() {
+        let cooldown = CommandCooldown::default();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(cooldown.allow("Gordon", Duration::from_secs(10), at(0)));
+        assert!(!cooldown.allow("Gordon", Duration::from_secs(10), at(5)));
+        assert!(cooldown.allow("Alyx", Duration::from_secs(10), at(5)));
+
+        // Lowering the timeout applies to the next command
+        assert!(cooldown.allow("Gordon", Duration::from_secs(2), at(6)));
+        assert!(!cooldown.allow("Gordon", Duration::from_secs(2), at(7)));
+    }
+}
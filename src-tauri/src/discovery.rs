@@ -0,0 +1,345 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+
+use crate::model::GameParser;
+
+/// A log modified within this window is considered actively written by a running game.
+const ACTIVE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Where Steam keeps its data on Linux, relative to the home directory
+const STEAM_ROOTS: [&str; 4] = [
+    ".steam/steam",
+    ".steam/root",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+];
+
+/// A Steam game and the console log it writes when logging is enabled
+struct SteamGame {
+    parser: GameParser,
+    install_dir: &'static str,
+    log_path: &'static str,
+    hint: &'static str,
+}
+
+const STEAM_GAMES: [SteamGame; 7] = [
+    SteamGame {
+        parser: GameParser::CounterStrike2,
+        install_dir: "Counter-Strike Global Offensive",
+        log_path: "game/csgo/console.log",
+        hint: "Add -condebug to the launch options",
+    },
+    SteamGame {
+        parser: GameParser::CounterStrikeSource,
+        install_dir: "Counter-Strike Source",
+        log_path: "cstrike/console.log",
+        hint: "Add -condebug to the launch options",
+    },
+    SteamGame {
+        parser: GameParser::TeamFortress2,
+        install_dir: "Team Fortress 2",
+        log_path: "tf/console.log",
+        hint: "Add -condebug to the launch options",
+    },
+    SteamGame {
+        parser: GameParser::GarrysMod,
+        install_dir: "GarrysMod",
+        log_path: "garrysmod/console.log",
+        hint: "Add -condebug to the launch options",
+    },
+    SteamGame {
+        parser: GameParser::Left4Dead2,
+        install_dir: "Left 4 Dead 2",
+        log_path: "left4dead2/console.log",
+        hint: "Add -condebug to the launch options",
+    },
+    SteamGame {
+        parser: GameParser::HalfLife2Deathmatch,
+        install_dir: "Half-Life 2 Deathmatch",
+        log_path: "hl2mp/console.log",
+        hint: "Add -condebug to the launch options",
+    },
+    // GoldSrc writes qconsole.log next to the engine instead of into the mod folder
+    SteamGame {
+        parser: GameParser::CounterStrike16,
+        install_dir: "Half-Life",
+        log_path: "qconsole.log",
+        hint: "Add -condebug to the launch options",
+    },
+];
+
+#[derive(Clone, Serialize)]
+pub struct LogCandidate {
+    pub parser: GameParser,
+    pub path: String,
+    pub exists: bool,
+    /// Whether the log was written to recently, i.e. the game is probably running
+    pub active: bool,
+    /// What to do if the game isn't writing the log yet
+    pub hint: Option<String>,
+}
+
+impl LogCandidate {
+    fn new(parser: GameParser, path: PathBuf, hint: &str) -> Self {
+        let modified = fs::metadata(&path).and_then(|metadata| metadata.modified());
+        let exists = path.is_file();
+
+        Self {
+            parser,
+            path: path.to_string_lossy().to_string(),
+            exists,
+            active: modified.is_ok_and(is_recent),
+            hint: (!exists).then(|| hint.to_string()),
+        }
+    }
+}
+
+fn is_recent(modified: SystemTime) -> bool {
+    SystemTime::now()
+        .duration_since(modified)
+        .map_or(true, |age| age <= ACTIVE_WINDOW)
+}
+
+/// Finds console logs of installed games
+///
+/// # Arguments
+/// home - The user's home directory
+///
+/// # Returns
+/// Candidate log files, active ones first
+pub fn discover(home: &Path) -> Vec<LogCandidate> {
+    let mut candidates = Vec::new();
+
+    for library in steam_libraries(home) {
+        for game in &STEAM_GAMES {
+            let install_dir = library.join("steamapps/common").join(game.install_dir);
+
+            if install_dir.is_dir() {
+                candidates.push(LogCandidate::new(
+                    game.parser.clone(),
+                    install_dir.join(game.log_path),
+                    game.hint,
+                ));
+            }
+        }
+    }
+
+    for minecraft_dir in minecraft_dirs(home) {
+        candidates.push(LogCandidate::new(
+            GameParser::Minecraft,
+            minecraft_dir.join("logs/latest.log"),
+            "Start Minecraft once to create the log",
+        ));
+    }
+
+    candidates.sort_by_key(|candidate| (!candidate.active, !candidate.exists));
+
+    candidates
+}
+
+/// Every Steam library folder, including the Steam root itself
+fn steam_libraries(home: &Path) -> Vec<PathBuf> {
+    let mut libraries: Vec<PathBuf> = Vec::new();
+
+    for root in STEAM_ROOTS.iter().map(|root| home.join(root)) {
+        if !root.is_dir() {
+            continue;
+        }
+
+        let mut found = vec![root.clone()];
+
+        for vdf in ["steamapps/libraryfolders.vdf", "config/libraryfolders.vdf"] {
+            if let Ok(contents) = fs::read_to_string(root.join(vdf)) {
+                found.extend(parse_library_folders(&contents));
+            }
+        }
+
+        // The roots are usually symlinks to the same directory
+        for library in found {
+            let library = fs::canonicalize(&library).unwrap_or(library);
+
+            if !libraries.contains(&library) {
+                libraries.push(library);
+            }
+        }
+    }
+
+    libraries
+}
+
+/// Extracts library paths from a `libraryfolders.vdf` file
+///
+/// Newer files nest a `"path"` key inside each numbered library, older
+/// files map the number straight to the path.
+pub fn parse_library_folders(vdf: &str) -> Vec<PathBuf> {
+    let tokens = vdf_tokens(vdf);
+    let mut paths = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match tokens[i].as_str() {
+            "{" => depth += 1,
+            "}" => depth -= 1,
+            key => {
+                let value = tokens
+                    .get(i + 1)
+                    .filter(|value| *value != "{" && *value != "}");
+
+                if let Some(value) = value {
+                    let is_new_format = key.eq_ignore_ascii_case("path");
+                    let is_old_format = depth == 1 && key.parse::<u32>().is_ok();
+
+                    if is_new_format || is_old_format {
+                        paths.push(PathBuf::from(value));
+                    }
+
+                    i += 1;
+                }
+            }
+        }
+
+        i += 1;
+    }
+
+    paths
+}
+
+/// Splits a VDF (Valve KeyValues) document into quoted strings and braces
+fn vdf_tokens(vdf: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = vdf.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' | '}' => tokens.push(ch.to_string()),
+            '"' => {
+                let mut token = String::new();
+
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' => token.extend(chars.next()),
+                        _ => token.push(ch),
+                    }
+                }
+
+                tokens.push(token);
+            }
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+/// The vanilla launcher's directory and any Prism/MultiMC style instances
+fn minecraft_dirs(home: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![home.join(".minecraft")];
+
+    for launcher in [".local/share/PrismLauncher", ".local/share/multimc"] {
+        if let Ok(instances) = fs::read_dir(home.join(launcher).join("instances")) {
+            dirs.extend(
+                instances
+                    .flatten()
+                    .map(|instance| instance.path().join(".minecraft")),
+            );
+        }
+    }
+
+    dirs.into_iter().filter(|dir| dir.is_dir()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_library_folders() {
+        let vdf = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/user/.local/share/Steam"
+		"label"		""
+		"apps"
+		{
+			"730"		"37225458435"
+		}
+	}
+	"1"
+	{
+		"path"		"/mnt/games/SteamLibrary"
+	}
+}
+"#;
+
+        assert_eq!(
+            parse_library_folders(vdf),
+            vec![
+                PathBuf::from("/home/user/.local/share/Steam"),
+                PathBuf::from("/mnt/games/SteamLibrary")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_old_library_folders() {
+        let vdf = r#"
+"LibraryFolders"
+{
+	"TimeNextStatsReport"		"1700000000"
+	"ContentStatsID"		"-1234"
+	"1"		"/mnt/games/SteamLibrary"
+}
+"#;
+
+        assert_eq!(
+            parse_library_folders(vdf),
+            vec![PathBuf::from("/mnt/games/SteamLibrary")]
+        );
+    }
+
+    #[test]
+    fn test_discover() {
+        let home = std::env::temp_dir().join(format!("source-cmd-gui-{}", uuid::Uuid::new_v4()));
+        let library = home.join("games");
+        let steam = home.join(".local/share/Steam");
+
+        fs::create_dir_all(steam.join("steamapps")).unwrap();
+        fs::write(
+            steam.join("steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\" {{ \"0\" {{ \"path\" \"{}\" }} }}",
+                library.display()
+            ),
+        )
+        .unwrap();
+
+        let cs2 = library.join("steamapps/common/Counter-Strike Global Offensive/game/csgo");
+        fs::create_dir_all(&cs2).unwrap();
+        fs::write(cs2.join("console.log"), "").unwrap();
+
+        fs::create_dir_all(steam.join("steamapps/common/Counter-Strike Source")).unwrap();
+        fs::create_dir_all(home.join(".minecraft")).unwrap();
+
+        let candidates = discover(&home);
+
+        assert_eq!(candidates.len(), 3);
+        assert!(matches!(candidates[0].parser, GameParser::CounterStrike2));
+        assert!(candidates[0].exists && candidates[0].active);
+        assert!(candidates[0].hint.is_none());
+        assert!(candidates[1..].iter().all(|candidate| !candidate.exists));
+        assert!(candidates[1..]
+            .iter()
+            .all(|candidate| candidate.hint.is_some()));
+
+        fs::remove_dir_all(home).unwrap();
+    }
+}
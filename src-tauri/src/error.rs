@@ -1,4 +1,8 @@
-use serde::Serialize;
+use std::time::Duration;
+
+use serde::{ser::SerializeStruct, Serialize};
+
+use crate::config::{format_field_errors, ConfigFieldError};
 
 pub type SourceCmdGuiResult<T = ()> = std::result::Result<T, SourceCmdGuiError>;
 
@@ -10,6 +14,12 @@ pub enum SourceCmdGuiError {
     #[error("SourceCmdParser is already running")]
     ProcessAlreadyRunning,
 
+    #[error("The parser stopped: {0}")]
+    ParserExited(String),
+
+    #[error("The parser did not stop within {:.3} seconds", .0.as_secs_f64())]
+    StopTimedOut(Duration),
+
     #[error(transparent)]
     TauriError(#[from] tauri::Error),
 
@@ -30,6 +40,45 @@ pub enum SourceCmdGuiError {
 
     #[error("The {0} script was not found.")]
     ScriptNotFound(String),
+
+    #[error("The {0} profile was not found.")]
+    ProfileNotFound(String),
+
+    #[error("A profile named {0} already exists.")]
+    ProfileAlreadyExists(String),
+
+    #[error("Profile names cannot be empty.")]
+    InvalidProfileName,
+
+    #[error(transparent)]
+    KeyringError(#[from] keyring::Error),
+
+    #[error("Secret store error: {0}")]
+    SecretStoreError(String),
+
+    #[error("The parser pattern is invalid: {0}")]
+    InvalidParserPattern(String),
+
+    #[error("RCON error: {0}")]
+    RconError(String),
+
+    #[error(transparent)]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Translation failed: {0}")]
+    TranslationError(String),
+
+    #[error("The config file is invalid: {0}")]
+    InvalidConfigFile(String),
+
+    #[error("Invalid config: {}", format_field_errors(.0))]
+    InvalidConfig(Vec<ConfigFieldError>),
+
+    #[error("Invalid override for {0}: {1}")]
+    InvalidConfigOverride(String, String),
+
+    #[error("Config version {0} is newer than the supported version {1}")]
+    UnsupportedConfigVersion(u32, u32),
 }
 
 impl SourceCmdGuiError {
@@ -43,6 +92,44 @@ impl Serialize for SourceCmdGuiError {
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        match self {
+            // The frontend shows these next to the fields they belong to
+            SourceCmdGuiError::InvalidConfig(fields) => {
+                let mut error = serializer.serialize_struct("SourceCmdGuiError", 3)?;
+                error.serialize_field("kind", "InvalidConfig")?;
+                error.serialize_field("message", &self.to_string())?;
+                error.serialize_field("fields", fields)?;
+                error.end()
+            }
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_invalid_config_is_serialized_per_field() {
+        let error = SourceCmdGuiError::InvalidConfig(vec![ConfigFieldError {
+            field: "owner".to_string(),
+            message: "The owner cannot be empty".to_string(),
+        }]);
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "kind": "InvalidConfig",
+                "message": "Invalid config: owner: The owner cannot be empty",
+                "fields": [{ "field": "owner", "message": "The owner cannot be empty" }]
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(SourceCmdGuiError::InvalidProfileName).unwrap(),
+            json!("Profile names cannot be empty.")
+        );
     }
 }
@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{debug, error, warn};
+use tokio::sync::Mutex;
+
+use crate::{
+    error::SourceCmdGuiResult,
+    identity::{self, PlayerIds},
+    model::state::{AppState, CommandResponse, Config},
+    output::{OutgoingQueue, Priority},
+    parsers::{EventParser, GameEvent, Whispers},
+    python,
+    repository::{ScriptRepository, StatsRepository},
+    runtime,
+    watcher::LogTailer,
+};
+
+/// How often the log is read for new events
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Kills this close together count towards the same multi-kill
+const MULTI_KILL_WINDOW: Duration = Duration::from_secs(10);
+
+/// A built-in reaction to game events, toggled like a command
+pub trait EventHandler: Send {
+    /// # Arguments
+    /// event - The event that happened
+    /// is_owner - Whether a player is the user, by name or Steam ID
+    ///
+    /// # Returns
+    /// A message to send to the chat
+    fn handle(&mut self, event: &GameEvent, is_owner: &dyn Fn(&str) -> bool) -> Option<String>;
+}
+
+pub struct EventCommand {
+    pub handler: Box<dyn EventHandler>,
+    pub name: String,
+    pub id: String,
+    pub description: String,
+}
+
+impl EventCommand {
+    fn new(handler: Box<dyn EventHandler>, name: &str, id: &str, description: &str) -> Self {
+        Self {
+            handler,
+            name: name.to_string(),
+            id: id.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+impl From<EventCommand> for CommandResponse {
+    fn from(command: EventCommand) -> Self {
+        Self {
+            enabled: true,
+            name: command.name,
+            description: command.description,
+            id: command.id,
+        }
+    }
+}
+
+pub fn get_event_commands() -> Vec<EventCommand> {
+    vec![
+        EventCommand::new(
+            Box::new(Greeter),
+            "Greeter",
+            "greet",
+            "Welcomes players joining the server",
+        ),
+        EventCommand::new(
+            Box::<MultiKillAnnouncer>::default(),
+            "Multi-Kill Announcer",
+            "multikill",
+            "Announces double kills and better",
+        ),
+    ]
+}
+
+struct Greeter;
+
+impl EventHandler for Greeter {
+    fn handle(&mut self, event: &GameEvent, is_owner: &dyn Fn(&str) -> bool) -> Option<String> {
+        match event {
+            GameEvent::PlayerConnected { player } if !is_owner(player) => {
+                Some(format!("Welcome, {}!", player))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MultiKillAnnouncer {
+    /// Kills in a row and when the last one happened, by killer
+    streaks: HashMap<String, (u32, Instant)>,
+}
+
+impl EventHandler for MultiKillAnnouncer {
+    fn handle(&mut self, event: &GameEvent, _is_owner: &dyn Fn(&str) -> bool) -> Option<String> {
+        match event {
+            GameEvent::Kill { killer, victim, .. } => {
+                self.streaks.remove(victim);
+
+                let now = Instant::now();
+                let (kills, last_kill) = self.streaks.entry(killer.clone()).or_insert((0, now));
+
+                if now.duration_since(*last_kill) > MULTI_KILL_WINDOW {
+                    *kills = 0;
+                }
+
+                *kills += 1;
+                *last_kill = now;
+
+                let title = match *kills {
+                    2 => "Double kill",
+                    3 => "Triple kill",
+                    4 => "Quad kill",
+                    5.. => "Rampage",
+                    _ => return None,
+                };
+
+                Some(format!("{} by {}!", title, killer))
+            }
+            GameEvent::RoundStart | GameEvent::MapChange { .. } => {
+                self.streaks.clear();
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reads game events from the log and hands them to event commands and scripts
+///
+/// Runs until the future is dropped, read errors are logged and retried.
+///
+/// # Arguments
+/// tailer - Reads the log from where events should start
+/// config - The config the parser was started with
+/// state - The app state
+/// queue - The outgoing response queue
+pub async fn dispatch(
+    mut tailer: LogTailer,
+    config: Config,
+    state: Arc<Mutex<AppState>>,
+    queue: Arc<OutgoingQueue>,
+) -> Infallible {
+    let parser = EventParser::new(&config.parser);
+    // Its own player IDs and whispers, the command parser already records them
+    let parser = match config.parser.get_parser(
+        &config.custom_parser,
+        &PlayerIds::default(),
+        &Whispers::default(),
+    ) {
+        Ok(chat) => parser.with_chat(chat),
+        Err(e) => {
+            warn!(
+                "Game events may include chat, the chat parser failed: {}",
+                e
+            );
+            parser
+        }
+    };
+    let mut commands = get_event_commands();
+
+    loop {
+        match tailer.read_lines() {
+            Ok(lines) => {
+                for event in lines.iter().filter_map(|line| parser.parse(line)) {
+                    if let Err(e) =
+                        handle_event(&event, &mut commands, &config, &state, &queue).await
+                    {
+                        error!("Error handling {}: {}", event.trigger(), e);
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to read game events: {}", e),
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn handle_event(
+    event: &GameEvent,
+    commands: &mut [EventCommand],
+    config: &Config,
+    state: &Arc<Mutex<AppState>>,
+    queue: &OutgoingQueue,
+) -> SourceCmdGuiResult {
+    debug!("Game event: {:?}", event);
+
+    let (live_config, player_ids, scripts, python_context, rcon) = {
+        let mut state = state.lock().await;
+
+        if !state
+            .config
+            .disabled_commands
+            .contains(&"stats".to_string())
+        {
+            state.stats_repository.record_event(event).await?;
+        }
+
+        (
+            state.config.clone(),
+            state.cmd_state.player_ids.clone(),
+            state.script_repository.get_scripts().await?,
+            state.cmd_state.python_context.clone(),
+            state.cmd_state.rcon.clone(),
+        )
+    };
+
+    // Read live, so a changed owner applies without a restart
+    let is_owner = |player: &str| identity::is_owner(player, &live_config, &player_ids);
+    let mut responses: Vec<String> = commands
+        .iter_mut()
+        .filter(|command| !live_config.disabled_commands.contains(&command.id))
+        .filter_map(|command| command.handler.handle(event, &is_owner))
+        .collect();
+
+    if !live_config
+        .disabled_commands
+        .contains(&"python".to_string())
+    {
+        for script in scripts
+            .iter()
+            .filter(|script| script.enabled && script.trigger == event.trigger())
+        {
+            let (response, context) = python::process_python_event(
+                script,
+                event,
+                &live_config,
+                python_context.clone(),
+                rcon.clone(),
+            )
+            .await?;
+
+            if let Some(context) = context {
+                state
+                    .lock()
+                    .await
+                    .cmd_state
+                    .python_context
+                    .override_values(&context);
+            }
+
+            responses.extend(response.map(|response| response.message));
+        }
+    }
+
+    let max_chat_length = config.parser.get_max_chat_length(&config.custom_parser);
+
+    for response in responses {
+        queue.push(
+            runtime::limit_length(response, max_chat_length),
+            None,
+            Priority::Low,
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nobody(_player: &str) -> bool {
+        false
+    }
+
+    fn kill(killer: &str, victim: &str) -> GameEvent {
+        GameEvent::Kill {
+            killer: killer.to_string(),
+            victim: victim.to_string(),
+            weapon: None,
+            headshot: false,
+        }
+    }
+
+    #[test]
+    fn test_multi_kill_announcer() {
+        let mut announcer = MultiKillAnnouncer::default();
+
+        assert_eq!(announcer.handle(&kill("a", "b"), &nobody), None);
+        assert_eq!(
+            announcer.handle(&kill("a", "c"), &nobody),
+            Some("Double kill by a!".to_string())
+        );
+        assert_eq!(
+            announcer.handle(&kill("a", "d"), &nobody),
+            Some("Triple kill by a!".to_string())
+        );
+
+        // Dying ends the streak
+        assert_eq!(announcer.handle(&kill("e", "a"), &nobody), None);
+        assert_eq!(announcer.handle(&kill("a", "f"), &nobody), None);
+    }
+
+    #[test]
+    fn test_greeter_skips_owner() {
+        let connected = |player: &str| GameEvent::PlayerConnected {
+            player: player.to_string(),
+        };
+        let config = Config {
+            owner: "Gordon".to_string(),
+            owner_steam_id: "STEAM_0:1:1234".to_string(),
+            ..Config::default()
+        };
+        let player_ids = PlayerIds::default();
+        player_ids.record("Gordon (AFK)", "[U:1:2469]");
+
+        let is_owner = |player: &str| identity::is_owner(player, &config, &player_ids);
+
+        assert_eq!(
+            Greeter.handle(&connected("Alyx"), &is_owner),
+            Some("Welcome, Alyx!".to_string())
+        );
+        assert_eq!(Greeter.handle(&connected("Gordon"), &is_owner), None);
+        assert_eq!(Greeter.handle(&connected("Gordon (AFK)"), &is_owner), None);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{collections::HashMap, fmt};
+
+use super::{functions, ErrorKind, ExpressionError, CONSTANTS};
+
+/// Factorials above this don't fit in an f64
+const MAX_FACTORIAL: f64 = 170.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryOperator::Add => write!(f, "+"),
+            BinaryOperator::Subtract => write!(f, "-"),
+            BinaryOperator::Multiply => write!(f, "*"),
+            BinaryOperator::Divide => write!(f, "/"),
+            BinaryOperator::Remainder => write!(f, "%"),
+            BinaryOperator::Power => write!(f, "^"),
+        }
+    }
+}
+
+/// A parsed expression
+///
+/// Nodes that can fail to evaluate keep the position they were parsed at, for the error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    /// A constant or a variable of the player
+    Variable {
+        name: String,
+        position: usize,
+    },
+    Negate(Box<Expr>),
+    Factorial {
+        operand: Box<Expr>,
+        position: usize,
+    },
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Call {
+        function: String,
+        arguments: Vec<Expr>,
+    },
+}
+
+impl Expr {
+    /// How many operators, calls and factorials there are, not counting negation so `-1`
+    /// is still just a number
+    pub fn operations(&self) -> usize {
+        match self {
+            Expr::Number(_) | Expr::Variable { .. } => 0,
+            Expr::Negate(operand) => operand.operations(),
+            Expr::Factorial { operand, .. } => 1 + operand.operations(),
+            Expr::Binary { left, right, .. } => 1 + left.operations() + right.operations(),
+            Expr::Call { arguments, .. } => {
+                1 + arguments.iter().map(Expr::operations).sum::<usize>()
+            }
+        }
+    }
+
+    /// # Arguments
+    /// variables - Values of the names that aren't constants
+    ///
+    /// # Returns
+    /// The value, which may be infinite or NaN, e.g. after dividing by zero
+    pub fn evaluate(&self, variables: &HashMap<String, f64>) -> Result<f64, ExpressionError> {
+        Ok(match self {
+            Expr::Number(value) => *value,
+            Expr::Variable { name, position } => CONSTANTS
+                .iter()
+                .find(|(constant, _)| constant == name)
+                .map(|(_, value)| *value)
+                .or_else(|| variables.get(name).copied())
+                .ok_or_else(|| {
+                    ExpressionError::new(ErrorKind::UnknownVariable(name.clone()), *position)
+                })?,
+            Expr::Negate(operand) => -operand.evaluate(variables)?,
+            Expr::Factorial { operand, position } => {
+                let value = operand.evaluate(variables)?;
+
+                if value.fract() != 0.0 || !(0.0..=MAX_FACTORIAL).contains(&value) {
+                    return Err(ExpressionError::new(ErrorKind::InvalidFactorial, *position));
+                }
+
+                (2..=value as u32).map(f64::from).product()
+            }
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = left.evaluate(variables)?;
+                let right = right.evaluate(variables)?;
+
+                match operator {
+                    BinaryOperator::Add => left + right,
+                    BinaryOperator::Subtract => left - right,
+                    BinaryOperator::Multiply => left * right,
+                    BinaryOperator::Divide => left / right,
+                    BinaryOperator::Remainder => left % right,
+                    BinaryOperator::Power => left.powf(right),
+                }
+            }
+            Expr::Call {
+                function,
+                arguments,
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.evaluate(variables))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                functions::call(function, &arguments)
+            }
+        })
+    }
+}
+
+/// Writes the expression back out with every operation in parentheses
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(value) => write!(f, "{}", value),
+            Expr::Variable { name, .. } => write!(f, "{}", name),
+            Expr::Negate(operand) => write!(f, "(-{})", operand),
+            Expr::Factorial { operand, .. } => write!(f, "({}!)", operand),
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => write!(f, "({} {} {})", left, operator, right),
+            Expr::Call {
+                function,
+                arguments,
+            } => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect();
+
+                write!(f, "{}({})", function, arguments.join(", "))
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/// Function names with the fewest and most arguments they take
+const FUNCTIONS: [(&str, usize, usize); 29] = [
+    ("sqrt", 1, 1),
+    ("cbrt", 1, 1),
+    ("abs", 1, 1),
+    ("exp", 1, 1),
+    ("ln", 1, 1),
+    // `log(x)` is base 10, `log(x, base)` any other
+    ("log", 1, 2),
+    ("log10", 1, 1),
+    ("log2", 1, 1),
+    ("sin", 1, 1),
+    ("cos", 1, 1),
+    ("tan", 1, 1),
+    ("asin", 1, 1),
+    ("acos", 1, 1),
+    ("atan", 1, 1),
+    ("atan2", 2, 2),
+    ("sinh", 1, 1),
+    ("cosh", 1, 1),
+    ("tanh", 1, 1),
+    ("asinh", 1, 1),
+    ("acosh", 1, 1),
+    ("atanh", 1, 1),
+    ("floor", 1, 1),
+    ("ceil", 1, 1),
+    ("round", 1, 1),
+    ("trunc", 1, 1),
+    ("signum", 1, 1),
+    ("hypot", 2, 2),
+    ("min", 1, usize::MAX),
+    ("max", 1, usize::MAX),
+];
+
+pub fn is_function(name: &str) -> bool {
+    FUNCTIONS.iter().any(|(function, _, _)| *function == name)
+}
+
+/// # Returns
+/// Whether the function takes this many arguments, `false` for unknown functions
+pub fn accepts(name: &str, count: usize) -> bool {
+    FUNCTIONS
+        .iter()
+        .any(|(function, min, max)| *function == name && (*min..=*max).contains(&count))
+}
+
+/// Calls a function the parser already checked the name and arguments of
+pub fn call(name: &str, arguments: &[f64]) -> f64 {
+    let x = arguments[0];
+
+    match name {
+        "sqrt" => x.sqrt(),
+        "cbrt" => x.cbrt(),
+        "abs" => x.abs(),
+        "exp" => x.exp(),
+        "ln" => x.ln(),
+        "log" => match arguments.get(1) {
+            Some(base) => x.log(*base),
+            None => x.log10(),
+        },
+        "log10" => x.log10(),
+        "log2" => x.log2(),
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        "asin" => x.asin(),
+        "acos" => x.acos(),
+        "atan" => x.atan(),
+        "atan2" => x.atan2(arguments[1]),
+        "sinh" => x.sinh(),
+        "cosh" => x.cosh(),
+        "tanh" => x.tanh(),
+        "asinh" => x.asinh(),
+        "acosh" => x.acosh(),
+        "atanh" => x.atanh(),
+        "floor" => x.floor(),
+        "ceil" => x.ceil(),
+        "round" => x.round(),
+        "trunc" => x.trunc(),
+        "signum" => x.signum(),
+        "hypot" => x.hypot(arguments[1]),
+        "min" => arguments.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        // The parser rejects unknown functions, so this is never reached
+        _ => f64::NAN,
+    }
+}
@@ -0,0 +1,66 @@
+mod ast;
+mod functions;
+mod parser;
+mod tokenizer;
+
+pub use ast::Expr;
+pub use parser::parse;
+
+/// Names that always have the same value
+const CONSTANTS: [(&str, f64); 3] = [
+    ("pi", std::f64::consts::PI),
+    ("e", std::f64::consts::E),
+    ("tau", std::f64::consts::TAU),
+];
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    #[error("unexpected character '{0}'")]
+    UnexpectedCharacter(char),
+
+    #[error("invalid number {0}")]
+    InvalidNumber(String),
+
+    #[error("unexpected \"{0}\"")]
+    UnexpectedToken(String),
+
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+
+    #[error("unclosed parenthesis")]
+    UnclosedParenthesis,
+
+    #[error("unknown function {0}")]
+    UnknownFunction(String),
+
+    #[error("wrong number of arguments for {0}")]
+    WrongArguments(String),
+
+    #[error("unknown variable {0}")]
+    UnknownVariable(String),
+
+    #[error("factorials need a whole number from 0 to 170")]
+    InvalidFactorial,
+}
+
+/// Why an expression could not be parsed or evaluated
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("{kind} at character {}", .position + 1)]
+pub struct ExpressionError {
+    pub kind: ErrorKind,
+    /// Index of the character the error is at, counting from 0
+    pub position: usize,
+}
+
+impl ExpressionError {
+    pub fn new(kind: ErrorKind, position: usize) -> Self {
+        Self { kind, position }
+    }
+}
+
+/// Whether a name is taken by a constant, a function or `x` for multiplication
+pub fn is_reserved(name: &str) -> bool {
+    name == "x"
+        || functions::is_function(name)
+        || CONSTANTS.iter().any(|(constant, _)| *constant == name)
+}
@@ -0,0 +1,368 @@
+use super::{
+    ast::{BinaryOperator, Expr},
+    functions,
+    tokenizer::{tokenize, Token, TokenKind},
+    ErrorKind, ExpressionError,
+};
+
+/// Parses an expression
+///
+/// From lowest to highest precedence: `+ -`, then `* / %` (and `x` between two operands),
+/// then unary minus, then `^` (right associative) and finally `!`.
+///
+/// # Arguments
+/// input - The expression, all of it has to be math
+///
+/// # Returns
+/// The expression, or what is wrong with it and where
+pub fn parse(input: &str) -> Result<Expr, ExpressionError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        index: 0,
+        end: input.chars().count(),
+    };
+
+    let expr = parser.sum()?;
+
+    match parser.peek() {
+        Some(token) => Err(unexpected(token)),
+        None => Ok(expr),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    /// Position of the end of the input, for errors about missing tokens
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Result<Token, ExpressionError> {
+        let token = self
+            .tokens
+            .get(self.index)
+            .cloned()
+            .ok_or_else(|| ExpressionError::new(ErrorKind::UnexpectedEnd, self.end))?;
+
+        self.index += 1;
+
+        Ok(token)
+    }
+
+    /// Moves past the next token if it is `kind`
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        let matches = self.peek().is_some_and(|token| token.kind == *kind);
+
+        if matches {
+            self.index += 1;
+        }
+
+        matches
+    }
+
+    fn sum(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.product()?;
+
+        loop {
+            let operator = match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::Plus) => BinaryOperator::Add,
+                Some(TokenKind::Minus) => BinaryOperator::Subtract,
+                _ => return Ok(left),
+            };
+
+            self.index += 1;
+            left = binary(operator, left, self.product()?);
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.unary()?;
+
+        loop {
+            let operator = match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::Star) => BinaryOperator::Multiply,
+                Some(TokenKind::Slash) => BinaryOperator::Divide,
+                Some(TokenKind::Percent) => BinaryOperator::Remainder,
+                // Only after an operand, so `x` on its own is still a name
+                Some(TokenKind::Identifier(name)) if name == "x" => BinaryOperator::Multiply,
+                _ => return Ok(left),
+            };
+
+            self.index += 1;
+            left = binary(operator, left, self.unary()?);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        if self.eat(&TokenKind::Minus) {
+            Ok(Expr::Negate(Box::new(self.unary()?)))
+        } else if self.eat(&TokenKind::Plus) {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, ExpressionError> {
+        let base = self.factorial()?;
+
+        if self.eat(&TokenKind::Caret) {
+            // Through unary, so `2^-1` works and `2^3^2` is `2^(3^2)`
+            Ok(binary(BinaryOperator::Power, base, self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn factorial(&mut self) -> Result<Expr, ExpressionError> {
+        let mut operand = self.primary()?;
+
+        while let Some(Token {
+            kind: TokenKind::Bang,
+            position,
+        }) = self.peek()
+        {
+            operand = Expr::Factorial {
+                operand: Box::new(operand),
+                position: *position,
+            };
+            self.index += 1;
+        }
+
+        Ok(operand)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        let token = self.next()?;
+
+        match token.kind {
+            TokenKind::Number(value) => Ok(Expr::Number(value)),
+            TokenKind::Identifier(name) if self.eat(&TokenKind::LeftParenthesis) => {
+                self.call(name, token.position)
+            }
+            TokenKind::Identifier(name) => Ok(Expr::Variable {
+                name,
+                position: token.position,
+            }),
+            TokenKind::LeftParenthesis => {
+                let expr = self.sum()?;
+
+                self.close(token.position)?;
+
+                Ok(expr)
+            }
+            _ => Err(unexpected(&token)),
+        }
+    }
+
+    /// Parses the arguments of a call, the opening parenthesis already eaten
+    fn call(&mut self, function: String, position: usize) -> Result<Expr, ExpressionError> {
+        if !functions::is_function(&function) {
+            return Err(ExpressionError::new(
+                ErrorKind::UnknownFunction(function),
+                position,
+            ));
+        }
+
+        let mut arguments = Vec::new();
+
+        if !self.eat(&TokenKind::RightParenthesis) {
+            loop {
+                arguments.push(self.sum()?);
+
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+
+            self.close(position)?;
+        }
+
+        if !functions::accepts(&function, arguments.len()) {
+            return Err(ExpressionError::new(
+                ErrorKind::WrongArguments(function),
+                position,
+            ));
+        }
+
+        Ok(Expr::Call {
+            function,
+            arguments,
+        })
+    }
+
+    /// Expects the parenthesis opened at `opened`
+    fn close(&mut self, opened: usize) -> Result<(), ExpressionError> {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::RightParenthesis,
+                ..
+            }) => {
+                self.index += 1;
+                Ok(())
+            }
+            Some(token) => Err(unexpected(token)),
+            None => Err(ExpressionError::new(ErrorKind::UnclosedParenthesis, opened)),
+        }
+    }
+}
+
+fn binary(operator: BinaryOperator, left: Expr, right: Expr) -> Expr {
+    Expr::Binary {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+fn unexpected(token: &Token) -> ExpressionError {
+    ExpressionError::new(
+        ErrorKind::UnexpectedToken(token.kind.to_string()),
+        token.position,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn evaluate(input: &str) -> Result<f64, ExpressionError> {
+        let variables = HashMap::from([("hp".to_string(), 73.0)]);
+
+        parse(input)?.evaluate(&variables)
+    }
+
+    fn error(kind: ErrorKind, position: usize) -> Result<f64, ExpressionError> {
+        Err(ExpressionError::new(kind, position))
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            evaluate("3 + 4 * 2 / (1 - 5) ^ 2 ^ 3"),
+            Ok(3.0 + 8.0 / 65536.0)
+        );
+        assert_eq!(evaluate("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(evaluate("2 ^ -1"), Ok(0.5));
+        assert_eq!(evaluate("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(evaluate("3! + 2 x 3"), Ok(12.0));
+        assert_eq!(evaluate("max(1, hp, 2) - min(4, 5)"), Ok(69.0));
+        assert_eq!(evaluate("log(100) + log2(8)"), Ok(5.0));
+        assert_eq!(evaluate("2 * pi / tau"), Ok(1.0));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(evaluate("2 +"), error(ErrorKind::UnexpectedEnd, 3));
+        assert_eq!(
+            evaluate("2 + * 3"),
+            error(ErrorKind::UnexpectedToken("*".to_string()), 4)
+        );
+        assert_eq!(
+            evaluate("3 - 1 gg"),
+            error(ErrorKind::UnexpectedToken("gg".to_string()), 6)
+        );
+        assert_eq!(evaluate("(1 + 2"), error(ErrorKind::UnclosedParenthesis, 0));
+        assert_eq!(
+            evaluate("1 + foo(2)"),
+            error(ErrorKind::UnknownFunction("foo".to_string()), 4)
+        );
+        assert_eq!(
+            evaluate("atan2(1)"),
+            error(ErrorKind::WrongArguments("atan2".to_string()), 0)
+        );
+        assert_eq!(
+            evaluate("hp + armor"),
+            error(ErrorKind::UnknownVariable("armor".to_string()), 5)
+        );
+        assert_eq!(evaluate("2.5!"), error(ErrorKind::InvalidFactorial, 3));
+        assert_eq!(
+            ExpressionError::new(ErrorKind::UnexpectedEnd, 3).to_string(),
+            "unexpected end of expression at character 4"
+        );
+    }
+
+    fn expression() -> impl Strategy<Value = Expr> {
+        let leaf = prop_oneof![
+            (0u32..100_000, 0u32..4).prop_map(|(digits, scale)| {
+                Expr::Number(f64::from(digits) / 10f64.powi(scale as i32))
+            }),
+            prop::sample::select(vec!["hp", "pi", "e"]).prop_map(|name| Expr::Variable {
+                name: name.to_string(),
+                position: 0,
+            }),
+        ];
+
+        leaf.prop_recursive(6, 48, 3, |inner| {
+            prop_oneof![
+                (
+                    prop::sample::select(vec![
+                        BinaryOperator::Add,
+                        BinaryOperator::Subtract,
+                        BinaryOperator::Multiply,
+                        BinaryOperator::Divide,
+                        BinaryOperator::Remainder,
+                        BinaryOperator::Power,
+                    ]),
+                    inner.clone(),
+                    inner.clone(),
+                )
+                    .prop_map(|(operator, left, right)| binary(operator, left, right)),
+                inner
+                    .clone()
+                    .prop_map(|operand| Expr::Negate(Box::new(operand))),
+                inner.clone().prop_map(|operand| Expr::Factorial {
+                    operand: Box::new(operand),
+                    position: 0,
+                }),
+                prop::collection::vec(inner, 1..4).prop_map(|arguments| Expr::Call {
+                    function: "max".to_string(),
+                    arguments,
+                }),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(input in "\\PC{0,40}") {
+            if let Err(error) = parse(&input) {
+                prop_assert!(error.position <= input.chars().count());
+            }
+        }
+
+        #[test]
+        fn test_parse_arithmetic_never_panics(input in "[0-9a-z .,()+*/%^!x-]{0,40}") {
+            if let Ok(expr) = parse(&input) {
+                let _ = expr.evaluate(&HashMap::new());
+            }
+        }
+
+        #[test]
+        fn test_display_round_trips(expr in expression()) {
+            let text = expr.to_string();
+            let parsed = parse(&text);
+
+            prop_assert!(parsed.is_ok(), "{} failed to parse: {:?}", text, parsed);
+            prop_assert_eq!(parsed.unwrap().to_string(), text);
+        }
+
+        #[test]
+        fn test_integer_arithmetic(a in -1000i32..1000, b in -1000i32..1000, c in -1000i32..1000) {
+            let (fa, fb, fc) = (f64::from(a), f64::from(b), f64::from(c));
+
+            prop_assert_eq!(evaluate(&format!("{} + {} * {}", a, b, c)), Ok(fa + fb * fc));
+            prop_assert_eq!(evaluate(&format!("{} - {} - {}", a, b, c)), Ok(fa - fb - fc));
+            prop_assert_eq!(evaluate(&format!("({} - {}) x {}", a, b, c)), Ok((fa - fb) * fc));
+        }
+    }
+}
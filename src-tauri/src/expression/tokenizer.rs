@@ -0,0 +1,246 @@
+use std::fmt;
+
+use super::{ErrorKind, ExpressionError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    Identifier(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    Bang,
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Number(value) => write!(f, "{}", value),
+            TokenKind::Identifier(name) => write!(f, "{}", name),
+            TokenKind::Plus => write!(f, "+"),
+            TokenKind::Minus => write!(f, "-"),
+            TokenKind::Star => write!(f, "*"),
+            TokenKind::Slash => write!(f, "/"),
+            TokenKind::Percent => write!(f, "%"),
+            TokenKind::Caret => write!(f, "^"),
+            TokenKind::Bang => write!(f, "!"),
+            TokenKind::LeftParenthesis => write!(f, "("),
+            TokenKind::RightParenthesis => write!(f, ")"),
+            TokenKind::Comma => write!(f, ","),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Index of the first character in the input
+    pub position: usize,
+}
+
+/// Splits an expression into tokens
+///
+/// # Arguments
+/// input - The expression, all of it has to be math
+///
+/// # Returns
+/// The tokens, or where the first character that can't be part of an expression is
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let position = index;
+        let kind = match chars[index] {
+            c if c.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '0'..='9' | '.' => {
+                let (value, end) = read_number(&chars, index)?;
+                index = end;
+                TokenKind::Number(value)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                // `3x4` is multiplication, not 3 followed by a name `x4`
+                let is_times = c == 'x'
+                    && index > 0
+                    && chars[index - 1].is_ascii_digit()
+                    && matches!(chars.get(index + 1), Some(next) if next.is_ascii_digit());
+
+                index += 1;
+
+                if !is_times {
+                    while index < chars.len()
+                        && (chars[index].is_alphanumeric() || chars[index] == '_')
+                    {
+                        index += 1;
+                    }
+                }
+
+                TokenKind::Identifier(chars[position..index].iter().collect())
+            }
+            c => {
+                let kind = match c {
+                    '+' => TokenKind::Plus,
+                    '-' | '−' => TokenKind::Minus,
+                    '*' | '×' | '·' => TokenKind::Star,
+                    '/' | '÷' => TokenKind::Slash,
+                    '%' => TokenKind::Percent,
+                    '^' => TokenKind::Caret,
+                    '!' => TokenKind::Bang,
+                    '(' => TokenKind::LeftParenthesis,
+                    ')' => TokenKind::RightParenthesis,
+                    ',' => TokenKind::Comma,
+                    other => {
+                        return Err(ExpressionError::new(
+                            ErrorKind::UnexpectedCharacter(other),
+                            position,
+                        ))
+                    }
+                };
+
+                index += 1;
+                kind
+            }
+        };
+
+        tokens.push(Token { kind, position });
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a decimal number with an optional exponent, or a `0x`/`0b` literal
+///
+/// # Returns
+/// The value and the index after the number
+fn read_number(chars: &[char], start: usize) -> Result<(f64, usize), ExpressionError> {
+    let digit_at =
+        |index: usize, radix: u32| matches!(chars.get(index), Some(c) if c.is_digit(radix));
+
+    if chars[start] == '0' {
+        let radix = match chars.get(start + 1) {
+            Some('x' | 'X') => 16,
+            Some('b' | 'B') => 2,
+            _ => 10,
+        };
+
+        if radix != 10 && digit_at(start + 2, radix) {
+            let mut end = start + 2;
+
+            while digit_at(end, radix) {
+                end += 1;
+            }
+
+            let digits: String = chars[start + 2..end].iter().collect();
+
+            return u64::from_str_radix(&digits, radix)
+                .map(|value| (value as f64, end))
+                .map_err(|_| invalid_number(chars, start, end));
+        }
+    }
+
+    let mut end = start;
+
+    while end < chars.len() && (chars[end].is_ascii_digit() || chars[end] == '.') {
+        end += 1;
+    }
+
+    // An exponent only when digits follow, so `2e` stays 2 and the constant e
+    if matches!(chars.get(end), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(end + 1), Some('+' | '-')));
+
+        if digit_at(end + 1 + sign, 10) {
+            end += 1 + sign;
+
+            while digit_at(end, 10) {
+                end += 1;
+            }
+        }
+    }
+
+    let text: String = chars[start..end].iter().collect();
+
+    text.parse()
+        .map(|value| (value, end))
+        .map_err(|_| invalid_number(chars, start, end))
+}
+
+fn invalid_number(chars: &[char], start: usize, end: usize) -> ExpressionError {
+    ExpressionError::new(
+        ErrorKind::InvalidNumber(chars[start..end].iter().collect()),
+        start,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            kinds("3 + 4.5 * max(2, 1e3) ^ 2!"),
+            vec![
+                TokenKind::Number(3.0),
+                TokenKind::Plus,
+                TokenKind::Number(4.5),
+                TokenKind::Star,
+                TokenKind::Identifier("max".to_string()),
+                TokenKind::LeftParenthesis,
+                TokenKind::Number(2.0),
+                TokenKind::Comma,
+                TokenKind::Number(1000.0),
+                TokenKind::RightParenthesis,
+                TokenKind::Caret,
+                TokenKind::Number(2.0),
+                TokenKind::Bang,
+            ]
+        );
+        assert_eq!(
+            kinds("3x4 + 0xff - 0b11 + exp"),
+            vec![
+                TokenKind::Number(3.0),
+                TokenKind::Identifier("x".to_string()),
+                TokenKind::Number(4.0),
+                TokenKind::Plus,
+                TokenKind::Number(255.0),
+                TokenKind::Minus,
+                TokenKind::Number(3.0),
+                TokenKind::Plus,
+                TokenKind::Identifier("exp".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert_eq!(
+            tokenize("1 + 1.2.3"),
+            Err(ExpressionError::new(
+                ErrorKind::InvalidNumber("1.2.3".to_string()),
+                4
+            ))
+        );
+        assert_eq!(
+            tokenize("10:30"),
+            Err(ExpressionError::new(ErrorKind::UnexpectedCharacter(':'), 2))
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::model::state::Config;
+
+/// The first 64-bit Steam ID, account ids are counted from here
+const STEAM_ID64_BASE: u64 = 76561197960265728;
+
+/// Steam IDs of players seen in the log, for parsers whose lines include them
+#[derive(Clone, Default)]
+pub struct PlayerIds {
+    ids: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl PlayerIds {
+    pub fn record(&self, name: &str, steam_id: &str) {
+        self.ids
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), steam_id.to_string());
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.ids.lock().unwrap().get(name).cloned()
+    }
+}
+
+/// The account number shared by every Steam ID format
+///
+/// # Arguments
+/// steam_id - `STEAM_0:1:1234`, `[U:1:2469]` or `76561197960268197`
+pub fn account_id(steam_id: &str) -> Option<u64> {
+    let steam_id = steam_id.trim();
+
+    if let Some(legacy) = steam_id.strip_prefix("STEAM_") {
+        let mut parts = legacy.split(':').skip(1).map(str::parse::<u64>);
+
+        return match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(y @ 0..=1)), Some(Ok(z)), None) => Some(z * 2 + y),
+            _ => None,
+        };
+    }
+
+    if let Some(steam3) = steam_id
+        .strip_prefix("[U:")
+        .and_then(|id| id.strip_suffix(']'))
+    {
+        return steam3.split_once(':')?.1.parse().ok();
+    }
+
+    steam_id
+        .parse::<u64>()
+        .ok()
+        .and_then(|id| id.checked_sub(STEAM_ID64_BASE))
+}
+
+/// Whether a message was sent by the owner
+///
+/// Compares Steam IDs when the owner's is configured and the parser provided
+/// the sender's, otherwise the whole name has to match.
+///
+/// # Arguments
+/// user_name - Who sent the message
+/// config - The config with the owner's name and Steam ID
+/// player_ids - Steam IDs seen in the log
+pub fn is_owner(user_name: &str, config: &Config, player_ids: &PlayerIds) -> bool {
+    let owner_id = account_id(&config.owner_steam_id);
+    let player_id = player_ids
+        .get(user_name)
+        .and_then(|steam_id| account_id(&steam_id));
+
+    match (owner_id, player_id) {
+        (Some(owner_id), Some(player_id)) => owner_id == player_id,
+        _ => user_name.trim() == config.owner.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_id_formats() {
+        assert_eq!(account_id("STEAM_0:1:1234"), Some(2469));
+        assert_eq!(account_id("STEAM_1:1:1234"), Some(2469));
+        assert_eq!(account_id("[U:1:2469]"), Some(2469));
+        assert_eq!(account_id("76561197960268197"), Some(2469));
+        assert_eq!(account_id("STEAM_0:2:1234"), None);
+        assert_eq!(account_id("BOT"), None);
+        assert_eq!(account_id(""), None);
+    }
+
+    #[test]
+    fn test_is_owner() {
+        let config = Config {
+            owner: "Gordon".to_string(),
+            owner_steam_id: "STEAM_0:1:1234".to_string(),
+            ..Default::default()
+        };
+        let player_ids = PlayerIds::default();
+
+        assert!(is_owner("Gordon", &config, &player_ids));
+        assert!(!is_owner("[BM] Gordon", &config, &player_ids));
+        assert!(!is_owner("Gordon Freeman", &config, &player_ids));
+
+        // A renamed owner is still recognised by their Steam ID, an impostor isn't
+        player_ids.record("Freeman", "[U:1:2469]");
+        player_ids.record("Gordon", "STEAM_1:0:99");
+
+        assert!(is_owner("Freeman", &config, &player_ids));
+        assert!(!is_owner("Gordon", &config, &player_ids));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+/// A command sending more responses than this within `LOOP_WINDOW` is halted
+const MAX_RESPONSES: usize = 5;
+
+const LOOP_WINDOW: Duration = Duration::from_secs(10);
+
+/// How long a halted command stays quiet
+const HALT_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct CommandResponses {
+    sent: VecDeque<Instant>,
+    halted_until: Option<Instant>,
+}
+
+/// Halts commands that respond too often, e.g. two bots answering each other forever
+#[derive(Default)]
+pub struct LoopBreaker {
+    commands: Mutex<HashMap<String, CommandResponses>>,
+}
+
+impl LoopBreaker {
+    /// Records a response of a command
+    ///
+    /// # Arguments
+    /// command - The command's id
+    /// now - When the response was made
+    ///
+    /// # Returns
+    /// Whether the response may be sent, `false` while the command is halted
+    pub fn allow(&self, command: &str, now: Instant) -> bool {
+        let mut commands = self.commands.lock().unwrap();
+        let responses = commands.entry(command.to_string()).or_default();
+
+        if let Some(halted_until) = responses.halted_until {
+            if now < halted_until {
+                return false;
+            }
+
+            responses.halted_until = None;
+            responses.sent.clear();
+        }
+
+        responses
+            .sent
+            .retain(|sent| now.duration_since(*sent) < LOOP_WINDOW);
+        responses.sent.push_back(now);
+
+        if responses.sent.len() > MAX_RESPONSES {
+            warn!(
+                "{} responded {} times in {} seconds, halting it for {} seconds",
+                command,
+                responses.sent.len(),
+                LOOP_WINDOW.as_secs(),
+                HALT_DURATION.as_secs()
+            );
+
+            responses.halted_until = Some(now + HALT_DURATION);
+
+            return false;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halts_command_firing_too_often() {
+        let breaker = LoopBreaker::default();
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        for i in 0..MAX_RESPONSES as u64 {
+            assert!(breaker.allow("mimic", at(i * 100)));
+        }
+
+        assert!(!breaker.allow("mimic", at(600)));
+        assert!(!breaker.allow("mimic", at(30_000)));
+
+        // Other commands keep working while one is halted
+        assert!(breaker.allow(".ping", at(700)));
+
+        assert!(breaker.allow("mimic", at(600) + HALT_DURATION));
+    }
+
+    #[test]
+    fn test_spread_out_responses_are_allowed() {
+        let breaker = LoopBreaker::default();
+        let start = Instant::now();
+
+        for i in 0..20 {
+            assert!(breaker.allow("chatgpt", start + LOOP_WINDOW / 2 * i));
+        }
+    }
+}
@@ -1,82 +1,154 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod calculator;
 mod commands;
+mod config;
+mod cooldown;
+mod discovery;
 mod error;
-mod lexer;
+mod events;
+mod expression;
+mod identity;
 mod logger;
+mod loop_guard;
+mod math_detector;
 mod model;
+mod moderation;
+mod output;
+mod parsers;
+mod poll;
 mod python;
+mod random;
+mod rcon;
 pub(crate) mod repository;
+mod runtime;
+mod secrets;
+mod stats;
+mod supervisor;
+mod translation;
+mod watcher;
 
-use std::{
-    collections::HashMap,
-    env,
-    path::PathBuf,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    time::Duration,
-};
+use std::{collections::HashMap, env, path::PathBuf, sync::Arc, time::Duration};
 
+use calculator::Calculator;
 use chatgpt::prelude::ChatGPT;
+use config::{ConfigFieldError, ConfigOverrides};
 use error::{SourceCmdGuiError, SourceCmdGuiResult};
+use identity::PlayerIds;
 use lazy_static::lazy_static;
 use log::{info, warn};
 use logger::Log;
 use model::{
-    entity::Script,
-    state::{AppState, CmdState, CommandResponse, Config},
+    entity::{Profile, Script},
+    state::{AppState, CmdState, CommandResponse, Config, ConfigUpdate, RESTART_REQUIRED_FIELDS},
 };
+use moderation::{ModerationHit, Moderator};
 
+use output::{OutgoingQueue, QueuedMessage};
 use python::DynamicPythonCtx;
-use repository::{JsonRepository, ScriptRepository};
-use source_cmd_parser::log_parser::SourceCmdLogParser;
+use repository::{
+    JsonModerationRepository, JsonProfileRepository, JsonRepository, JsonStatsRepository,
+    ModerationRepository, ProfileRepository, ScriptRepository, StatsRepository,
+};
+use secrets::SharedSecretStore;
+use supervisor::{ParserStatus, ParserSupervisor};
 use tauri::{Manager, State};
 use tokio::{
     fs,
     sync::{mpsc, Mutex},
 };
 
+/// How long `stop` waits for the parser thread before giving up on it
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often player stats and the moderation log are saved, at most this much is lost if the
+/// app is closed
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
 lazy_static! {
     static ref CONFIG_DIR: PathBuf = {
-        let home_dir = dirs::home_dir().expect("Failed to get home directory");
+        let args: Vec<String> = env::args().collect();
 
-        home_dir.join(".source-cmd-gui/")
+        config::resolve_config_dir(&args, |key| env::var(key).ok()).unwrap_or_else(|| {
+            let home_dir = dirs::home_dir().expect("Failed to get home directory");
+
+            home_dir.join(".source-cmd-gui/")
+        })
     };
     static ref CONFIG_FILE: PathBuf = CONFIG_DIR.join("config.json");
     static ref SCRIPTS_DIR: PathBuf = CONFIG_DIR.join("scripts");
     static ref SCRIPTS_REPOSITORY: PathBuf = SCRIPTS_DIR.join("repo.json");
+    static ref PROFILES_REPOSITORY: PathBuf = CONFIG_DIR.join("profiles.json");
+    static ref STATS_REPOSITORY: PathBuf = CONFIG_DIR.join("stats.json");
+    static ref MODERATION_REPOSITORY: PathBuf = CONFIG_DIR.join("moderation.json");
 }
 
 #[tauri::command]
 async fn is_running(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult<bool> {
-    Ok(state.lock().await.running_thread.is_some())
+    Ok(state.lock().await.supervisor.is_active())
 }
 
-async fn load_or_create_config() -> SourceCmdGuiResult<Config> {
-    let config = Config::default();
+#[tauri::command]
+async fn get_status(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult<ParserStatus> {
+    Ok(state.lock().await.supervisor.status())
+}
 
-    fs::create_dir_all(SCRIPTS_DIR.to_string_lossy().to_string()).await?;
+/// The error the parser last crashed with, kept after it is restarted
+#[tauri::command]
+async fn get_last_error(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> SourceCmdGuiResult<Option<String>> {
+    Ok(state.lock().await.supervisor.last_error())
+}
+
+async fn load_or_create_config(secrets: &SharedSecretStore) -> SourceCmdGuiResult<Config> {
+    let config = Config::default();
 
-    // Load config from file
+    // Load config from file, upgrading older schemas
     if let Ok(config_json) = tokio::fs::read_to_string(CONFIG_FILE.clone()).await {
-        if let Ok(config) = serde_json::from_str::<Config>(&config_json) {
-            return Ok(config);
-        }
+        let migrated = serde_json::from_str::<serde_json::Value>(&config_json)
+            .map_err(SourceCmdGuiError::from)
+            .and_then(config::migrate);
+
+        return match migrated {
+            Ok(migrated) => {
+                if migrated.upgraded {
+                    for (name, value) in &migrated.secrets {
+                        secrets.set(name, value)?;
+                    }
+
+                    // Keep the original around in case the upgrade needs to be undone
+                    tokio::fs::write(CONFIG_DIR.join("config.json.bak"), config_json).await?;
+                    write_config(&migrated.config).await?;
+
+                    info!("Upgraded config to version {}", migrated.config.version);
+                }
+
+                Ok(migrated.config)
+            }
+            Err(e) => {
+                warn!("Failed to load config, using defaults: {}", e);
 
-        return Ok(config);
+                Ok(config)
+            }
+        };
     }
 
+    write_config(&config).await?;
+
+    Ok(config)
+}
+
+async fn write_config(config: &Config) -> SourceCmdGuiResult {
     // Save config to file as json
-    let config_json = serde_json::to_string(&config).unwrap();
+    let config_json = serde_json::to_string(config)?;
 
     tokio::fs::write(CONFIG_FILE.clone(), config_json).await?;
 
     info!("Saved config to file");
 
-    Ok(config)
+    Ok(())
 }
 
 #[tauri::command]
@@ -118,17 +190,307 @@ async fn save_code(
 }
 
 #[tauri::command]
-async fn save_config(state: State<'_, Arc<Mutex<AppState>>>, config: Config) -> SourceCmdGuiResult {
+async fn save_config(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    config: Config,
+) -> SourceCmdGuiResult<ConfigUpdate> {
     let mut state = state.lock().await;
 
-    state.config = config;
+    let previous = std::mem::replace(&mut state.config, config);
 
-    // Save config to file as json
-    let config_json = serde_json::to_string(&state.config).unwrap();
+    // Commands read the config from the state, so only the client and
+    // conversations built from it need refreshing
+    if state.active_config().is_some() {
+        if previous.openai_api_key_secret != state.config.openai_api_key_secret {
+            state.cmd_state.chat_gpt = build_chat_gpt(&state)?;
+        }
 
-    tokio::fs::write(CONFIG_FILE.clone(), config_json).await?;
+        if previous.response_direction != state.config.response_direction
+            || previous.openai_api_key_secret != state.config.openai_api_key_secret
+        {
+            state.cmd_state.conversations.clear();
+        }
+    }
 
-    info!("Saved config to file");
+    // Overridden fields keep their values from the config file
+    let mut persisted = state.config.clone();
+
+    if !state.config_overrides.is_empty() {
+        let on_disk = load_or_create_config(&state.secrets).await?;
+        state.config_overrides.restore(&mut persisted, &on_disk)?;
+    }
+
+    // Keep the active profile in sync with the settings being edited
+    if let Some(mut profile) = state.profile_repository.get_active_profile() {
+        profile.config = persisted.clone();
+        state.profile_repository.update_profile(profile).await?;
+    }
+
+    write_config(&persisted).await?;
+
+    let restart_required = state
+        .active_config()
+        .map(|running| state.config.restart_required_fields(running))
+        .unwrap_or_default();
+
+    Ok(ConfigUpdate { restart_required })
+}
+
+/// Fields that only take effect when the parser is (re)started
+#[tauri::command]
+fn get_restart_required_fields() -> Vec<&'static str> {
+    RESTART_REQUIRED_FIELDS.to_vec()
+}
+
+fn build_chat_gpt(state: &AppState) -> SourceCmdGuiResult<Option<ChatGPT>> {
+    let api_key = state
+        .secrets
+        .get(&state.config.openai_api_key_secret)?
+        .unwrap_or_default();
+
+    Ok(ChatGPT::new(api_key).ok())
+}
+
+#[tauri::command]
+async fn get_overridden_fields(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> SourceCmdGuiResult<Vec<String>> {
+    let state = state.lock().await;
+
+    Ok(state.config_overrides.fields())
+}
+
+async fn enabled_script_ids(state: &AppState) -> SourceCmdGuiResult<Vec<String>> {
+    Ok(state
+        .script_repository
+        .get_scripts()
+        .await?
+        .into_iter()
+        .filter(|script| script.enabled)
+        .map(|script| script.id)
+        .collect())
+}
+
+/// Makes a profile the active one
+///
+/// # Arguments
+/// state - The app state
+/// profile - The profile to activate
+///
+/// Replaces the current config with the profile's config and enables exactly
+/// the scripts listed in the profile.
+async fn apply_profile(state: &mut AppState, profile: &Profile) -> SourceCmdGuiResult {
+    for mut script in state.script_repository.get_scripts().await? {
+        let enabled = profile.enabled_scripts.contains(&script.id);
+
+        if script.enabled != enabled {
+            script.enabled = enabled;
+            state
+                .script_repository
+                .update_script(&script.id.clone(), script)
+                .await?;
+        }
+    }
+
+    state.config = profile.config.clone();
+    state.config_overrides.apply(&mut state.config)?;
+    state
+        .profile_repository
+        .set_active_profile(Some(&profile.name))
+        .await?;
+
+    write_config(&profile.config).await?;
+
+    info!("Activated profile {}", profile.name);
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_profiles(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult<Vec<Profile>> {
+    let state = state.lock().await;
+
+    Ok(state.profile_repository.get_profiles())
+}
+
+#[tauri::command]
+async fn get_active_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> SourceCmdGuiResult<Option<String>> {
+    let state = state.lock().await;
+
+    Ok(state
+        .profile_repository
+        .get_active_profile()
+        .map(|profile| profile.name))
+}
+
+/// Creates a profile from the current config and enabled scripts
+#[tauri::command]
+async fn create_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    name: String,
+) -> SourceCmdGuiResult<Profile> {
+    let mut state = state.lock().await;
+
+    let enabled_scripts = enabled_script_ids(&state).await?;
+    let profile = Profile::new(name, state.config.clone(), enabled_scripts);
+
+    info!("Creating profile: {:?}", profile.name);
+
+    state.profile_repository.add_profile(profile).await
+}
+
+#[tauri::command]
+async fn clone_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    source: &str,
+    name: String,
+) -> SourceCmdGuiResult<Profile> {
+    let mut state = state.lock().await;
+
+    let mut profile = state.profile_repository.get_profile(source)?;
+    profile.name = name;
+
+    state.profile_repository.add_profile(profile).await
+}
+
+#[tauri::command]
+async fn delete_profile(state: State<'_, Arc<Mutex<AppState>>>, name: &str) -> SourceCmdGuiResult {
+    let mut state = state.lock().await;
+
+    state.profile_repository.delete_profile(name).await
+}
+
+#[tauri::command]
+async fn activate_profile(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    name: &str,
+) -> SourceCmdGuiResult<Config> {
+    let mut state = state.lock().await;
+
+    if state.supervisor.is_active() {
+        return Err(SourceCmdGuiError::ProcessAlreadyRunning);
+    }
+
+    let profile = state.profile_repository.get_profile(name)?;
+    apply_profile(&mut state, &profile).await?;
+
+    Ok(state.config.clone())
+}
+
+#[tauri::command]
+async fn has_secret(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    name: &str,
+) -> SourceCmdGuiResult<bool> {
+    let state = state.lock().await;
+
+    Ok(state.secrets.get(name)?.is_some())
+}
+
+/// Stores a secret. Values are write-only from the frontend's point of view.
+#[tauri::command]
+async fn set_secret(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    name: &str,
+    value: &str,
+) -> SourceCmdGuiResult {
+    let mut state = state.lock().await;
+
+    if value.is_empty() {
+        state.secrets.delete(name)?;
+    } else {
+        state.secrets.set(name, value)?;
+    }
+
+    // Swap the key used by the running parser
+    if state.active_config().is_some() && name == state.config.openai_api_key_secret {
+        state.cmd_state.chat_gpt = build_chat_gpt(&state)?;
+        state.cmd_state.conversations.clear();
+    }
+
+    Ok(())
+}
+
+fn check_config(state: &AppState, config: &Config) -> SourceCmdGuiResult<Vec<ConfigFieldError>> {
+    let command_ids: Vec<String> = get_commands()
+        .into_iter()
+        .map(|command| command.id)
+        .collect();
+    let api_key = state.secrets.get(&config.openai_api_key_secret)?;
+    let rcon_password = state.secrets.get(&config.output.rcon_password_secret)?;
+
+    Ok(config::validate(
+        config,
+        &command_ids,
+        api_key.as_deref(),
+        rcon_password.as_deref(),
+    ))
+}
+
+#[tauri::command]
+async fn validate_config(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    config: Config,
+) -> SourceCmdGuiResult<Vec<ConfigFieldError>> {
+    let state = state.lock().await;
+
+    check_config(&state, &config)
+}
+
+/// Proposes console log files for installed games
+#[tauri::command]
+async fn discover_log_files() -> SourceCmdGuiResult<Vec<discovery::LogCandidate>> {
+    let Some(home_dir) = dirs::home_dir() else {
+        return Ok(vec![]);
+    };
+
+    Ok(tokio::task::spawn_blocking(move || discovery::discover(&home_dir)).await?)
+}
+
+/// Shows what a custom parser pattern captures from sample log lines
+#[tauri::command]
+fn test_parser_pattern(
+    pattern: &str,
+    lines: Vec<String>,
+) -> SourceCmdGuiResult<Vec<parsers::PatternMatch>> {
+    Ok(parsers::CustomParser::new(pattern)?.test(&lines))
+}
+
+/// Every player seen, most recently seen first
+#[tauri::command]
+async fn get_player_stats(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> SourceCmdGuiResult<Vec<stats::PlayerStats>> {
+    Ok(state.lock().await.stats_repository.get_players())
+}
+
+/// Messages the moderation filters caught, newest first
+#[tauri::command]
+async fn get_moderation_log(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> SourceCmdGuiResult<Vec<ModerationHit>> {
+    Ok(state.lock().await.moderation_repository.get_hits())
+}
+
+#[tauri::command]
+async fn clear_moderation_log(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult {
+    state.lock().await.moderation_repository.clear().await
+}
+
+/// Responses waiting to be sent, in the order they will be sent
+#[tauri::command]
+async fn get_outgoing_queue(
+    state: State<'_, Arc<Mutex<AppState>>>,
+) -> SourceCmdGuiResult<Vec<QueuedMessage>> {
+    Ok(state.lock().await.outgoing.pending())
+}
+
+/// Drops every response waiting to be sent
+#[tauri::command]
+async fn clear_outgoing_queue(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult {
+    state.lock().await.outgoing.clear();
 
     Ok(())
 }
@@ -138,81 +500,115 @@ fn get_commands() -> Vec<CommandResponse> {
     commands::get_commands()
         .into_iter()
         .map(|command| command.into())
+        .chain(
+            events::get_event_commands()
+                .into_iter()
+                .map(|command| command.into()),
+        )
         .collect()
 }
 
 #[tauri::command]
-async fn start(state: State<'_, Arc<Mutex<AppState>>>, config: Config) -> SourceCmdGuiResult {
+async fn start(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    config: Config,
+    profile: Option<String>,
+) -> SourceCmdGuiResult {
     let cloned_app_state = state.clone().inner().clone();
     let mut state = state.lock().await;
 
-    if state.running_thread.is_some() {
+    if state.supervisor.is_active() {
         return Err(SourceCmdGuiError::ProcessAlreadyRunning);
     }
 
-    state.stop_flag.store(false, Ordering::Relaxed);
+    // Starting with a profile ignores the config sent by the frontend
+    let (config, profile) = match profile {
+        Some(name) => {
+            let profile = state.profile_repository.get_profile(&name)?;
+            let mut config = profile.config.clone();
+            state.config_overrides.apply(&mut config)?;
 
-    let api_key = config.openai_api_key.clone();
+            (config, Some(profile))
+        }
+        None => (config, None),
+    };
+
+    let errors = check_config(&state, &config)?;
+
+    if !errors.is_empty() {
+        return Err(SourceCmdGuiError::InvalidConfig(errors));
+    }
+
+    // Only replaces the user's config and scripts once the profile's config is valid
+    if let Some(profile) = profile {
+        apply_profile(&mut state, &profile).await?;
+    }
+
+    // Commands read the live config from the state
+    state.config = config.clone();
+    state.running_config = Some(config.clone());
 
     let cmd_state = CmdState {
         personality: String::new(),
-        chat_gpt: ChatGPT::new(api_key).ok(),
+        chat_gpt: build_chat_gpt(&state)?,
         conversations: HashMap::new(),
         python_context: DynamicPythonCtx::default(),
+        rcon: None,
+        player_ids: PlayerIds::default(),
+        moderator: Moderator::default(),
+        calculator: Calculator::default(),
+        ..Default::default()
     };
 
     state.cmd_state = cmd_state;
+    state.outgoing.clear();
 
-    let stop_flag = state.stop_flag.clone();
-
-    let handle = std::thread::spawn(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let result = rt.block_on(async move {
-            let mut builder = SourceCmdLogParser::builder()
-                .file_path(Box::new(PathBuf::from(config.file_path)))
-                .state(cloned_app_state)
-                .set_parser(config.parser.get_parser())
-                .chat_key(config.parser.get_chat_key())
-                .stop_flag(stop_flag)
-                .owner(&config.owner)
-                .time_out(Duration::from_secs(config.command_timeout));
-
-            for command in commands::get_commands() {
-                if command.global_command {
-                    builder = builder.add_global_command(move |msg, state| {
-                        // Call the function in the trait object
-                        command.command.call(msg, state)
-                    });
-                } else {
-                    builder = builder.add_command(&command.id.to_string(), move |msg, state| {
-                        // Call the function in the trait object
-                        command.command.call(msg, state)
-                    });
-                }
-            }
+    state.supervisor.start(move |stop_flag, status| {
+        runtime::run_parser(config, cloned_app_state, stop_flag, status)
+    })
+}
 
-            let mut parser = builder.build()?;
+/// Stops the parser without holding the state lock while it shuts down
+#[tauri::command]
+async fn stop(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult {
+    let stopping = {
+        let mut state = state.lock().await;
+        state.running_config = None;
+        state.outgoing.clear();
+        state.cmd_state.stop_poll();
 
-            parser.run().await
-        });
+        state.supervisor.stop(STOP_TIMEOUT)
+    };
 
-        result.map_err(SourceCmdGuiError::SourceCmdParserError)
-    });
+    let stopped = stopping.await;
 
-    state.running_thread = Some(handle);
+    {
+        let mut state = state.lock().await;
+        state.stats_repository.flush().await?;
+        state.moderation_repository.flush().await?;
+    }
 
-    Ok(())
+    stopped
 }
 
-#[tauri::command]
-async fn stop(state: State<'_, Arc<Mutex<AppState>>>) -> SourceCmdGuiResult {
-    let mut state = state.lock().await;
-    if let Some(handle) = state.running_thread.take() {
-        state.stop_flag.store(true, Ordering::Relaxed);
-        handle.join().unwrap()?;
-    }
+/// Writes the player stats and moderation hits recorded since the last flush every
+/// `FLUSH_INTERVAL`
+async fn flush_repositories(state: Arc<Mutex<AppState>>) {
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
 
-    Ok(())
+    loop {
+        interval.tick().await;
+
+        let mut state = state.lock().await;
+
+        if let Err(e) = state.stats_repository.flush().await {
+            warn!("Failed to save player stats: {}", e);
+        }
+
+        if let Err(e) = state.moderation_repository.flush().await {
+            warn!("Failed to save the moderation log: {}", e);
+        }
+    }
 }
 
 #[tauri::command]
@@ -257,25 +653,60 @@ async fn update_script(
 #[tokio::main]
 async fn main() -> SourceCmdGuiResult {
     let (tx, mut rx) = mpsc::channel::<Log>(100);
+    let (status_tx, mut status_rx) = mpsc::channel::<ParserStatus>(100);
+    let (queue_tx, mut queue_rx) = mpsc::channel::<Vec<QueuedMessage>>(100);
 
     logger::setup_logger(tx);
 
-    let config = load_or_create_config().await?;
+    fs::create_dir_all(SCRIPTS_DIR.to_string_lossy().to_string()).await?;
+
+    let secrets = secrets::open_secret_store(&CONFIG_DIR);
+    let mut config = load_or_create_config(&secrets).await?;
+
+    let args: Vec<String> = env::args().collect();
+    let config_overrides = ConfigOverrides::new(&args, |key| env::var(key).ok());
+    config_overrides.apply(&mut config)?;
+
+    if !config_overrides.is_empty() {
+        info!(
+            "Overriding config fields: {}",
+            config_overrides.fields().join(", ")
+        );
+    }
 
     let mut app_state = AppState {
-        running_thread: None,
+        supervisor: ParserSupervisor::new(Some(status_tx)),
         config,
-        stop_flag: Arc::<AtomicBool>::default(),
+        running_config: None,
+        config_overrides,
         cmd_state: CmdState::default(),
         script_repository: JsonRepository::new(SCRIPTS_REPOSITORY.to_string_lossy().to_string())
             .await,
+        profile_repository: JsonProfileRepository::new(
+            PROFILES_REPOSITORY.to_string_lossy().to_string(),
+            secrets.clone(),
+        )
+        .await,
+        stats_repository: JsonStatsRepository::new(STATS_REPOSITORY.to_string_lossy().to_string())
+            .await,
+        moderation_repository: JsonModerationRepository::new(
+            MODERATION_REPOSITORY.to_string_lossy().to_string(),
+        )
+        .await,
+        secrets,
+        outgoing: Arc::new(OutgoingQueue::new(Some(queue_tx))),
     };
 
     // Setup database tables
     app_state.script_repository.init().await?;
+    app_state.profile_repository.init().await?;
+    app_state.stats_repository.init().await?;
+    app_state.moderation_repository.init().await?;
+
+    let state = Arc::new(Mutex::new(app_state));
 
     tauri::Builder::default()
-        .manage(Arc::new(Mutex::new(app_state)))
+        .manage(state.clone())
         .invoke_handler(tauri::generate_handler![
             is_running,
             get_config,
@@ -288,9 +719,31 @@ async fn main() -> SourceCmdGuiResult {
             delete_script,
             update_script,
             get_code,
-            save_code
+            save_code,
+            get_profiles,
+            get_active_profile,
+            create_profile,
+            clone_profile,
+            delete_profile,
+            activate_profile,
+            has_secret,
+            set_secret,
+            get_overridden_fields,
+            validate_config,
+            discover_log_files,
+            get_restart_required_fields,
+            get_status,
+            get_last_error,
+            test_parser_pattern,
+            get_player_stats,
+            get_moderation_log,
+            clear_moderation_log,
+            get_outgoing_queue,
+            clear_outgoing_queue
         ])
         .setup(move |app| {
+            tauri::async_runtime::spawn(flush_repositories(state));
+
             let app_handle = app.handle();
             tauri::async_runtime::spawn(async move {
                 while let Some(message) = rx.recv().await {
@@ -303,6 +756,24 @@ async fn main() -> SourceCmdGuiResult {
                 }
             });
 
+            let app_handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                while let Some(status) = status_rx.recv().await {
+                    if app_handle.emit_all("parser_status", &status).is_err() {
+                        warn!("Failed to send parser status to frontend");
+                    }
+                }
+            });
+
+            let app_handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                while let Some(queue) = queue_rx.recv().await {
+                    if app_handle.emit_all("outgoing_queue", &queue).is_err() {
+                        warn!("Failed to send outgoing queue to frontend");
+                    }
+                }
+            });
+
             Ok(())
         })
         .run(tauri::generate_context!())?;
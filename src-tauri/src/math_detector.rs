@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::expression;
+
+/// Words around math that suggest someone wants it worked out
+const QUESTION_WORDS: [&str; 14] = [
+    "what",
+    "whats",
+    "what's",
+    "is",
+    "calc",
+    "calculate",
+    "solve",
+    "equals",
+    "how",
+    "much",
+    "many",
+    "math",
+    "eval",
+    "compute",
+];
+
+/// Words around numbers that suggest a score, a time or a rating rather than math
+const NOISE_WORDS: [&str; 33] = [
+    "gg", "ez", "score", "won", "win", "lost", "lose", "round", "rounds", "map", "half", "at",
+    "am", "pm", "tonight", "today", "tomorrow", "time", "ping", "kd", "kda", "rating", "rate",
+    "rated", "lead", "up", "down", "vs", "us", "them", "game", "games", "again",
+];
+
+/// Math found in a chat line
+#[derive(Debug, Clone, PartialEq)]
+pub struct MathCandidate {
+    /// The part of the line that is math
+    pub expression: String,
+    pub value: f64,
+    /// How sure the detector is that the line is meant as math, from 0 to 100
+    pub score: u32,
+}
+
+/// Tells math apart from chat that happens to contain numbers and operators
+pub struct MathDetector {
+    /// `3-1`, `16 - 14`
+    game_score: Regex,
+    /// `2024-01-05`, `12/05/24`, `20/5/3` for kills, deaths and assists
+    date: Regex,
+    /// `7/10`, `20/5`
+    rating: Regex,
+}
+
+impl Default for MathDetector {
+    fn default() -> Self {
+        Self {
+            game_score: Regex::new(r"^\d{1,3}\s*-\s*\d{1,3}$").unwrap(),
+            date: Regex::new(r"^\d{1,4}[/.-]\d{1,2}[/.-]\d{1,4}$").unwrap(),
+            rating: Regex::new(r"^\d{1,2}/\d{1,2}$").unwrap(),
+        }
+    }
+}
+
+impl MathDetector {
+    /// Finds the math in a chat line and scores how likely it is meant to be worked out
+    ///
+    /// The score rises with how much of the line the math covers, how many operations it
+    /// has and question words around it, and falls for words like "gg" or "ping" and for
+    /// math that looks like a game score, a date or a rating.
+    ///
+    /// # Arguments
+    /// line - The chat line
+    /// variables - The variables of the player, math using other names isn't math
+    ///
+    /// # Returns
+    /// The longest run of words that evaluates and does at least one operation, `None` if
+    /// there is none
+    pub fn detect(&self, line: &str, variables: &HashMap<String, f64>) -> Option<MathCandidate> {
+        let words: Vec<(usize, &str)> = line
+            .split_whitespace()
+            .map(|word| (word.as_ptr() as usize - line.as_ptr() as usize, word))
+            .collect();
+
+        let mut best: Option<(usize, usize, f64, usize)> = None;
+
+        for start in 0..words.len() {
+            for end in (start + 1..=words.len()).rev() {
+                // Earlier runs win ties, so only a longer run replaces the best
+                if best
+                    .is_some_and(|(best_start, best_end, ..)| end - start <= best_end - best_start)
+                {
+                    break;
+                }
+
+                let (last_offset, last_word) = words[end - 1];
+                let text = &line[words[start].0..last_offset + last_word.len()];
+
+                if let Some((value, operations)) = evaluate(text, variables) {
+                    best = Some((start, end, value, operations));
+                    break;
+                }
+            }
+        }
+
+        let (start, end, value, operations) = best?;
+        let (last_offset, last_word) = words[end - 1];
+        let expression = &line[words[start].0..last_offset + last_word.len()];
+
+        let length = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count() as i32;
+        let mut score = 20 + 40 * length(expression) / length(line) + 10 * operations.min(3) as i32;
+
+        for (_, word) in words[..start].iter().chain(&words[end..]) {
+            let word = word
+                .trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+                .to_lowercase();
+
+            if QUESTION_WORDS.contains(&word.as_str()) {
+                score += 10;
+            } else if NOISE_WORDS.contains(&word.as_str()) {
+                score -= 15;
+            }
+        }
+
+        if self.game_score.is_match(expression) {
+            score -= 35;
+        }
+
+        if self.date.is_match(expression) {
+            score -= 50;
+        }
+
+        if self.rating.is_match(expression) {
+            score -= 30;
+        }
+
+        Some(MathCandidate {
+            expression: expression.to_string(),
+            value,
+            score: score.clamp(0, 100) as u32,
+        })
+    }
+}
+
+/// # Returns
+/// The value and number of operations, `None` if the text isn't math or only a number
+fn evaluate(text: &str, variables: &HashMap<String, f64>) -> Option<(f64, usize)> {
+    let expr = expression::parse(text).ok()?;
+    let operations = expr.operations();
+
+    if operations == 0 {
+        return None;
+    }
+
+    expr.evaluate(variables)
+        .ok()
+        .map(|value| (value, operations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lines seen in game chat, and whether they should be answered at the default sensitivity
+    const CORPUS: [(&str, bool); 40] = [
+        ("2+2", true),
+        ("2 + 2", true),
+        ("what is 2 + 2", true),
+        ("whats 15*4", true),
+        ("how much is 1200 / 7", true),
+        ("calc 3^4 - 1", true),
+        ("sqrt(2)", true),
+        ("2^10", true),
+        ("3 x 4", true),
+        ("12x12", true),
+        ("1.5 * 3", true),
+        ("(100 - 27) / 2", true),
+        ("max(250, 260) - 240", true),
+        ("5!", true),
+        ("0xff + 1", true),
+        ("hp / 2", true),
+        ("ans * 2", true),
+        ("1 / 3", true),
+        ("3-1", false),
+        ("16-14", false),
+        ("we won 16-14", false),
+        ("gg 3-1", false),
+        ("3 - 1 again", false),
+        ("its 13-2 at half", false),
+        ("10:30", false),
+        ("meet at 10:30 tonight", false),
+        ("2024-01-05", false),
+        ("12/05/2024", false),
+        ("kd 20/5/3", false),
+        ("7/10", false),
+        ("rate it 8/10", false),
+        ("im 20/5 today", false),
+        ("ping 120-130", false),
+        ("1v1 me", false),
+        ("5v5", false),
+        ("-1", false),
+        ("+1", false),
+        ("v1.2.3 is out", false),
+        ("100%", false),
+        ("gg - ez", false),
+    ];
+
+    #[test]
+    fn test_corpus() {
+        let detector = MathDetector::default();
+        let variables = HashMap::from([("hp".to_string(), 73.0), ("ans".to_string(), 4.0)]);
+
+        for (line, expected) in CORPUS {
+            let answered = detector
+                .detect(line, &variables)
+                .is_some_and(|candidate| candidate.score > 50);
+
+            assert_eq!(
+                answered,
+                expected,
+                "{}: {:?}",
+                line,
+                detector.detect(line, &variables)
+            );
+        }
+    }
+
+    #[test]
+    fn test_finds_the_math_in_a_line() {
+        let detector = MathDetector::default();
+        let candidate = detector
+            .detect("what is 2 + 2 * 3 guys", &HashMap::new())
+            .unwrap();
+
+        assert_eq!(candidate.expression, "2 + 2 * 3");
+        assert_eq!(candidate.value, 8.0);
+        assert_eq!(detector.detect("nice shot", &HashMap::new()), None);
+    }
+}
@@ -7,6 +7,8 @@ use uuid::Uuid;
 
 use crate::SCRIPTS_DIR;
 
+use super::state::Config;
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Script {
     pub id: String,
@@ -48,3 +50,22 @@ impl Script {
         Ok(())
     }
 }
+
+/// A named, full configuration together with the scripts enabled under it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub config: Config,
+    /// Ids of the scripts enabled while this profile is active
+    pub enabled_scripts: Vec<String>,
+}
+
+impl Profile {
+    pub fn new(name: String, config: Config, enabled_scripts: Vec<String>) -> Self {
+        Self {
+            name,
+            config,
+            enabled_scripts,
+        }
+    }
+}
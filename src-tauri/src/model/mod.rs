@@ -2,11 +2,20 @@ pub mod entity;
 pub mod state;
 
 use serde::{Deserialize, Serialize};
-use source_cmd_parser::parsers::{CSSLogParser, Cs2LogParser};
+use source_cmd_parser::{
+    log_parser::ParseLog,
+    parsers::{CSSLogParser, Cs2LogParser},
+};
 
-use crate::commands::MinecraftParser;
+use crate::{
+    error::SourceCmdGuiResult,
+    identity::PlayerIds,
+    parsers::{self, CustomParser, MinecraftParser, Whispers},
+};
 
-#[derive(Clone, Serialize, Deserialize)]
+use self::state::CustomParserConfig;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum GameParser {
     #[serde(rename = "Counter Strike 2")]
     CounterStrike2,
@@ -16,21 +25,164 @@ pub enum GameParser {
 
     #[serde(rename = "Minecraft")]
     Minecraft,
+
+    #[serde(rename = "Team Fortress 2")]
+    TeamFortress2,
+
+    #[serde(rename = "Garry's Mod")]
+    GarrysMod,
+
+    #[serde(rename = "Left 4 Dead 2")]
+    Left4Dead2,
+
+    #[serde(rename = "Half-Life 2: Deathmatch")]
+    HalfLife2Deathmatch,
+
+    #[serde(rename = "Counter-Strike 1.6")]
+    CounterStrike16,
+
+    /// Configured through `Config::custom_parser`
+    #[serde(rename = "Custom")]
+    Custom,
 }
 
 impl GameParser {
-    pub fn get_parser(&self) -> Box<dyn source_cmd_parser::log_parser::ParseLog> {
-        match self {
+    /// # Arguments
+    /// custom - The pattern for `GameParser::Custom`
+    /// player_ids - Where parsers that read Steam IDs record them
+    /// whispers - Where parsers that can tell whispers apart record them
+    pub fn get_parser(
+        &self,
+        custom: &CustomParserConfig,
+        player_ids: &PlayerIds,
+        whispers: &Whispers,
+    ) -> SourceCmdGuiResult<Box<dyn ParseLog>> {
+        Ok(match self {
             GameParser::CounterStrike2 => Box::<Cs2LogParser>::default(),
             GameParser::CounterStrikeSource => Box::<CSSLogParser>::default(),
-            GameParser::Minecraft => Box::<MinecraftParser>::default(),
-        }
+            GameParser::Minecraft => {
+                Box::new(MinecraftParser::new().with_whispers(whispers.clone()))
+            }
+            GameParser::TeamFortress2
+            | GameParser::Left4Dead2
+            | GameParser::HalfLife2Deathmatch
+            | GameParser::CounterStrike16 => Box::new(parsers::source_parser()),
+            GameParser::GarrysMod => Box::new(parsers::gmod_parser()),
+            GameParser::Custom => {
+                Box::new(CustomParser::new(&custom.pattern)?.with_player_ids(player_ids.clone()))
+            }
+        })
     }
 
-    pub fn get_chat_key(&self) -> enigo::Key {
+    pub fn get_chat_key(&self, custom: &CustomParserConfig) -> enigo::Key {
         match self {
-            GameParser::CounterStrike2 | GameParser::CounterStrikeSource => enigo::Key::Layout('y'),
+            GameParser::CounterStrike2
+            | GameParser::CounterStrikeSource
+            | GameParser::TeamFortress2
+            | GameParser::GarrysMod
+            | GameParser::Left4Dead2
+            | GameParser::HalfLife2Deathmatch
+            | GameParser::CounterStrike16 => enigo::Key::Layout('y'),
             GameParser::Minecraft => enigo::Key::Layout('t'),
+            GameParser::Custom => enigo::Key::Layout(custom.chat_key.chars().next().unwrap_or('y')),
         }
     }
+
+    /// The chat command replying privately to a player, if the game has one
+    pub fn get_whisper_command(&self) -> Option<&'static str> {
+        match self {
+            GameParser::Minecraft => Some("/msg"),
+            _ => None,
+        }
+    }
+
+    /// The longest response the game accepts, if it is known
+    pub fn get_max_chat_length(&self, custom: &CustomParserConfig) -> Option<usize> {
+        match self {
+            GameParser::Custom => Some(custom.max_chat_length),
+            // Longer messages are cut off by the engine
+            GameParser::TeamFortress2
+            | GameParser::GarrysMod
+            | GameParser::Left4Dead2
+            | GameParser::HalfLife2Deathmatch
+            | GameParser::CounterStrike16 => Some(127),
+            _ => None,
+        }
+    }
+}
+
+/// Where command and event responses are sent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum OutputKind {
+    /// Types into the game chat, the game has to be focused
+    #[default]
+    #[serde(rename = "Keystrokes")]
+    Keystrokes,
+
+    /// Writes a `say` command to a cfg file the game runs with `exec`
+    #[serde(rename = "Exec Cfg")]
+    ExecCfg,
+
+    /// Runs `say` on the server over RCON
+    #[serde(rename = "RCON")]
+    Rcon,
+
+    /// Runs `tellraw`, `say` or `msg` on a Minecraft server over RCON
+    #[serde(rename = "Minecraft RCON")]
+    MinecraftRcon,
+
+    /// Only logs responses, for dry runs
+    #[serde(rename = "Capture")]
+    Capture,
+}
+
+/// How `OutputKind::MinecraftRcon` replies
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum MinecraftReply {
+    /// Plain text to everyone with `tellraw @a`
+    #[default]
+    #[serde(rename = "Tellraw")]
+    Tellraw,
+
+    /// To everyone with `say`, prefixed with the server's name
+    #[serde(rename = "Say")]
+    Say,
+
+    /// Privately to the player who ran the command with `msg`, event responses go to everyone
+    #[serde(rename = "Private Message")]
+    PrivateMessage,
+}
+
+/// What the moderation filters do with a player who broke them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ModerationAction {
+    /// Only records the message in the moderation log
+    #[default]
+    #[serde(rename = "Log")]
+    Log,
+
+    /// Sends a warning in chat
+    #[serde(rename = "Warn")]
+    Warn,
+
+    /// Kicks the player over RCON, warns instead without an RCON connection
+    #[serde(rename = "Kick")]
+    Kick,
+
+    /// Mutes the player over RCON, warns instead without an RCON connection
+    #[serde(rename = "Mute")]
+    Mute,
+}
+
+/// What translates messages for `.tr` and auto-translate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TranslationBackend {
+    /// The ChatGPT client `explain` uses
+    #[default]
+    #[serde(rename = "ChatGPT")]
+    ChatGpt,
+
+    /// A LibreTranslate compatible HTTP endpoint, e.g. one running locally
+    #[serde(rename = "HTTP")]
+    Http,
 }
@@ -1,45 +1,300 @@
-use std::{
-    collections::HashMap,
-    sync::{atomic::AtomicBool, Arc},
-};
+use std::{collections::HashMap, sync::Arc};
 
 use chatgpt::{client::ChatGPT, converse::Conversation};
 
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
 
-use crate::{error::SourceCmdGuiResult, python::DynamicPythonCtx, repository::JsonRepository};
+use crate::{
+    calculator::Calculator,
+    config::{ConfigOverrides, CURRENT_CONFIG_VERSION},
+    identity::PlayerIds,
+    moderation::Moderator,
+    output::OutgoingQueue,
+    poll::Poll,
+    python::DynamicPythonCtx,
+    random::Randomizer,
+    rcon::RconClient,
+    repository::{
+        JsonModerationRepository, JsonProfileRepository, JsonRepository, JsonStatsRepository,
+    },
+    secrets::SharedSecretStore,
+    supervisor::ParserSupervisor,
+};
 
-use super::GameParser;
+use super::{GameParser, MinecraftReply, ModerationAction, OutputKind, TranslationBackend};
 
 pub struct AppState {
-    pub running_thread: Option<std::thread::JoinHandle<SourceCmdGuiResult>>,
+    pub supervisor: ParserSupervisor,
     pub config: Config,
-    pub stop_flag: Arc<AtomicBool>,
+    /// The config the running parser was started with
+    pub running_config: Option<Config>,
+    /// Environment and command line overrides layered on top of the config file
+    pub config_overrides: ConfigOverrides,
     pub cmd_state: CmdState,
     pub script_repository: JsonRepository,
+    pub profile_repository: JsonProfileRepository,
+    pub stats_repository: JsonStatsRepository,
+    pub moderation_repository: JsonModerationRepository,
+    pub secrets: SharedSecretStore,
+    /// Responses waiting to be sent, shared with the running parser
+    pub outgoing: Arc<OutgoingQueue>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    pub version: u32,
     pub file_path: String,
     pub command_timeout: u64,
     pub owner: String,
+    /// Matched instead of `owner` when the parser provides Steam IDs, in any format
+    pub owner_steam_id: String,
     pub parser: GameParser,
-    pub openai_api_key: String,
+    /// Only used with `GameParser::Custom`
+    pub custom_parser: CustomParserConfig,
+    /// Where responses are sent
+    pub output: OutputConfig,
+    /// Filters of the moderation command
+    pub moderation: ModerationConfig,
+    pub translation: TranslationConfig,
+    pub eval: EvalConfig,
+    pub poll: PollConfig,
+    /// Name of the OpenAI API key in the secret store
+    pub openai_api_key_secret: String,
     pub disabled_commands: Vec<String>,
     pub response_direction: String,
+    /// Restart the parser with backoff when it fails
+    pub auto_restart: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CURRENT_CONFIG_VERSION,
             file_path: String::from(""),
             command_timeout: 10,
             owner: String::from(""),
+            owner_steam_id: String::from(""),
             parser: GameParser::CounterStrike2,
-            openai_api_key: String::from(""),
+            custom_parser: CustomParserConfig::default(),
+            output: OutputConfig::default(),
+            moderation: ModerationConfig::default(),
+            translation: TranslationConfig::default(),
+            eval: EvalConfig::default(),
+            poll: PollConfig::default(),
+            openai_api_key_secret: String::from("openai_api_key"),
             disabled_commands: vec![],
             response_direction: "Keep the response to 120 chars".to_string(),
+            auto_restart: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomParserConfig {
+    /// Regex with `user` and `message` named groups, and optionally `channel`, `team`, `dead` and `steam_id`
+    pub pattern: String,
+    /// The key that opens the chat box
+    pub chat_key: String,
+    /// Responses are cut off at this many characters
+    pub max_chat_length: usize,
+}
+
+impl Default for CustomParserConfig {
+    fn default() -> Self {
+        Self {
+            pattern: String::from(r"^(?P<user>.+?): (?P<message>.+)$"),
+            chat_key: String::from("y"),
+            max_chat_length: 127,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub kind: OutputKind,
+    /// The cfg file written by `OutputKind::ExecCfg`, inside the game's `cfg` folder
+    pub exec_cfg_path: String,
+    /// The key bound to `exec` the cfg, left empty to press it yourself
+    pub exec_key: String,
+    /// `host:port` of the server for `OutputKind::Rcon` and `OutputKind::MinecraftRcon`
+    pub rcon_address: String,
+    /// Name of the RCON password in the secret store
+    pub rcon_password_secret: String,
+    /// How `OutputKind::MinecraftRcon` replies
+    pub minecraft_reply: MinecraftReply,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            kind: OutputKind::Keystrokes,
+            exec_cfg_path: String::from(""),
+            exec_key: String::from(""),
+            rcon_address: String::from("127.0.0.1:27015"),
+            rcon_password_secret: String::from("rcon_password"),
+            minecraft_reply: MinecraftReply::Tellraw,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// Words and phrases that are not allowed, matched as whole words ignoring case
+    pub banned_words: Vec<String>,
+    /// How many seconds the spam checks look back
+    pub spam_window: u64,
+    /// Sending more messages than this within `spam_window` is flooding, 0 allows any
+    pub max_messages: usize,
+    /// Sending the same message more often than this within `spam_window` is spam, 0 allows any
+    pub max_repeats: usize,
+    /// Messages with a larger share of capital letters are shouting, 0 allows any
+    pub max_caps_percent: u32,
+    /// Messages with fewer letters are never shouting
+    pub min_caps_length: usize,
+    pub action: ModerationAction,
+    /// Sent for `ModerationAction::Warn`, `{player}` and `{reason}` are replaced
+    pub warning: String,
+    /// RCON command for `ModerationAction::Kick`, `{player}` is replaced
+    pub kick_command: String,
+    /// RCON command for `ModerationAction::Mute`, `{player}` is replaced
+    pub mute_command: String,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            banned_words: vec![],
+            spam_window: 10,
+            max_messages: 6,
+            max_repeats: 3,
+            max_caps_percent: 70,
+            min_caps_length: 8,
+            action: ModerationAction::Log,
+            warning: String::from("{player}, please stop {reason}"),
+            kick_command: String::from("kick \"{player}\""),
+            mute_command: String::from("sm_mute \"{player}\""),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationConfig {
+    pub backend: TranslationBackend,
+    /// The `/translate` URL for `TranslationBackend::Http`
+    pub endpoint: String,
+    /// Translate messages from other players that are not in `target_language`
+    pub auto_translate: bool,
+    /// ISO 639-1 code of the language auto-translate translates to
+    pub target_language: String,
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            backend: TranslationBackend::ChatGpt,
+            endpoint: String::from("http://localhost:5000/translate"),
+            auto_translate: false,
+            target_language: String::from("en"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvalConfig {
+    /// Decimal places results are rounded to
+    pub precision: usize,
+    /// How much of each currency one US dollar buys, for conversions like `20 usd to eur`
+    pub currencies: HashMap<String, f64>,
+    /// From 0 to 100, how readily lines that only might be math are answered
+    pub sensitivity: u32,
+    /// Only answer lines starting with `=` or ending with `?`
+    pub explicit_only: bool,
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            precision: 4,
+            currencies: [
+                ("usd", 1.0),
+                ("eur", 0.92),
+                ("gbp", 0.79),
+                ("cad", 1.36),
+                ("aud", 1.52),
+                ("brl", 5.0),
+                ("pln", 4.0),
+                ("rub", 92.0),
+                ("uah", 39.0),
+                ("cny", 7.2),
+            ]
+            .into_iter()
+            .map(|(code, rate)| (code.to_string(), rate))
+            .collect(),
+            sensitivity: 50,
+            explicit_only: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollConfig {
+    /// Seconds a `.poll` collects votes before the results are posted
+    pub duration: u64,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self { duration: 60 }
+    }
+}
+
+impl AppState {
+    /// The config the parser was started with, if it is still running
+    pub fn active_config(&self) -> Option<&Config> {
+        self.running_config
+            .as_ref()
+            .filter(|_| self.supervisor.is_active())
+    }
+}
+
+/// Fields baked into the parser when it starts. Every other field is read
+/// from `AppState::config` as commands run, so edits apply immediately.
+pub const RESTART_REQUIRED_FIELDS: [&str; 4] = ["file_path", "parser", "custom_parser", "output"];
+
+impl Config {
+    /// The fields that differ from `running` and only take effect after a restart
+    pub fn restart_required_fields(&self, running: &Config) -> Vec<&'static str> {
+        let changed = [
+            self.file_path != running.file_path,
+            self.parser != running.parser,
+            self.custom_parser != running.custom_parser,
+            self.output != running.output,
+        ];
+
+        RESTART_REQUIRED_FIELDS
+            .into_iter()
+            .zip(changed)
+            .filter(|(_, changed)| *changed)
+            .map(|(field, _)| field)
+            .collect()
+    }
+
+    /// Points every secret reference named `from` at `to`
+    pub fn rename_secret(&mut self, from: &str, to: &str) {
+        if self.openai_api_key_secret == from {
+            self.openai_api_key_secret = to.to_string();
+        }
+
+        if self.output.rcon_password_secret == from {
+            self.output.rcon_password_secret = to.to_string();
         }
     }
 }
@@ -53,6 +308,45 @@ pub struct CmdState {
 
     // Dynamic context for python
     pub python_context: DynamicPythonCtx,
+
+    /// The server scripts can run commands on, set when the parser starts
+    pub rcon: Option<Arc<RconClient>>,
+
+    /// Steam IDs the parser read from the log
+    pub player_ids: PlayerIds,
+
+    /// Recent messages for the moderation spam checks
+    pub moderator: Moderator,
+
+    /// Variables of every player for `eval`
+    pub calculator: Calculator,
+
+    /// Dice, coins and picks
+    pub randomizer: Randomizer,
+
+    /// The `.poll` collecting votes, if any
+    pub poll: Option<Poll>,
+
+    /// Closes `poll` when its time is up
+    pub poll_timer: Option<JoinHandle<()>>,
+}
+
+impl CmdState {
+    /// Ends the running poll without posting its results, e.g. when the parser stops
+    pub fn stop_poll(&mut self) {
+        if let Some(timer) = self.poll_timer.take() {
+            timer.abort();
+        }
+
+        self.poll = None;
+    }
+}
+
+/// The result of saving the config while the parser may be running
+#[derive(Clone, Serialize)]
+pub struct ConfigUpdate {
+    /// Changed fields that the running parser will only pick up after a restart
+    pub restart_required: Vec<&'static str>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -0,0 +1,298 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{state::ModerationConfig, ModerationAction};
+
+/// Older hits are dropped from the moderation log
+const MAX_LOG_LEN: usize = 1000;
+
+/// Why a message was moderated
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    BannedWord(String),
+    Flooding,
+    Repeated,
+    Caps,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::BannedWord(word) => write!(f, "banned word \"{}\"", word),
+            Violation::Flooding => write!(f, "flooding the chat"),
+            Violation::Repeated => write!(f, "repeating the same message"),
+            Violation::Caps => write!(f, "excessive caps"),
+        }
+    }
+}
+
+/// Recent messages of every player, for spotting spam
+#[derive(Default)]
+pub struct Moderator {
+    players: HashMap<String, VecDeque<(String, Instant)>>,
+}
+
+impl Moderator {
+    /// Checks a message against the filters and records it for the spam checks
+    ///
+    /// # Arguments
+    /// config - The filters to apply
+    /// user - Who sent the message
+    /// message - What they sent
+    /// now - When they sent it
+    ///
+    /// # Returns
+    /// The first filter the message broke, if any
+    pub fn check(
+        &mut self,
+        config: &ModerationConfig,
+        user: &str,
+        message: &str,
+        now: Instant,
+    ) -> Option<Violation> {
+        if let Some(word) = find_banned_word(&config.banned_words, message) {
+            return Some(Violation::BannedWord(word));
+        }
+
+        let window = Duration::from_secs(config.spam_window);
+        let normalized = message.trim().to_lowercase();
+
+        // Forget players who have been quiet for the whole window, or every name ever seen is kept
+        self.players.retain(|_, recent| {
+            recent.retain(|(_, sent)| now.duration_since(*sent) < window);
+            !recent.is_empty()
+        });
+
+        let recent = self.players.entry(user.to_string()).or_default();
+        recent.push_back((normalized.clone(), now));
+
+        let repeats = recent
+            .iter()
+            .filter(|(sent, _)| *sent == normalized)
+            .count();
+
+        let violation = if config.max_messages > 0 && recent.len() > config.max_messages {
+            Some(Violation::Flooding)
+        } else if config.max_repeats > 0 && repeats > config.max_repeats {
+            Some(Violation::Repeated)
+        } else if is_shouting(message, config.max_caps_percent, config.min_caps_length) {
+            Some(Violation::Caps)
+        } else {
+            None
+        };
+
+        // Start counting again, so a spammer is acted on once per burst rather than every message
+        if matches!(violation, Some(Violation::Flooding | Violation::Repeated)) {
+            recent.clear();
+        }
+
+        violation
+    }
+}
+
+/// Banned words and phrases only match whole words, ignoring case and punctuation
+fn find_banned_word(banned_words: &[String], message: &str) -> Option<String> {
+    let words = |text: &str| {
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let message = format!(" {} ", words(message));
+
+    banned_words
+        .iter()
+        .find(|banned| {
+            let banned = words(banned);
+
+            !banned.is_empty() && message.contains(&format!(" {} ", banned))
+        })
+        .cloned()
+}
+
+fn is_shouting(message: &str, max_caps_percent: u32, min_length: usize) -> bool {
+    let letters: Vec<char> = message.chars().filter(|c| c.is_alphabetic()).collect();
+
+    if max_caps_percent == 0 || letters.is_empty() || letters.len() < min_length {
+        return false;
+    }
+
+    let caps = letters.iter().filter(|c| c.is_uppercase()).count();
+
+    caps * 100 > letters.len() * max_caps_percent as usize
+}
+
+/// Fills in a kick or mute command
+///
+/// # Arguments
+/// template - The command with a `{player}` placeholder
+/// player - The player to act on
+///
+/// # Returns
+/// The command, with anything in the name that could end it or start another removed
+pub fn action_command(template: &str, player: &str) -> String {
+    let player: String = player
+        .chars()
+        .filter(|c| !matches!(c, '"' | ';' | '\n' | '\r'))
+        .collect();
+
+    template.replace("{player}", player.trim())
+}
+
+/// Fills in the warning sent to a player
+pub fn warning(template: &str, player: &str, violation: &Violation) -> String {
+    template
+        .replace("{player}", player)
+        .replace("{reason}", &violation.to_string())
+}
+
+/// A message the moderation filters caught
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationHit {
+    pub time: DateTime<Utc>,
+    pub player: String,
+    pub message: String,
+    pub reason: String,
+    /// What was done, a warning when kicking or muting was not possible
+    pub action: ModerationAction,
+}
+
+/// The most recent moderation hits, oldest first
+#[derive(Default, Serialize, Deserialize)]
+pub struct ModerationLog {
+    hits: VecDeque<ModerationHit>,
+}
+
+impl ModerationLog {
+    pub fn record(&mut self, hit: ModerationHit) {
+        self.hits.push_back(hit);
+
+        while self.hits.len() > MAX_LOG_LEN {
+            self.hits.pop_front();
+        }
+    }
+
+    /// Every hit, newest first
+    pub fn hits(&self) -> Vec<ModerationHit> {
+        self.hits.iter().rev().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.hits.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ModerationConfig {
+        ModerationConfig {
+            banned_words: vec!["noob".to_string(), "go away".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_banned_words_match_whole_words() {
+        let mut moderator = Moderator::default();
+        let config = config();
+        let now = Instant::now();
+        let mut check = |message: &str| moderator.check(&config, "Alyx", message, now);
+
+        assert_eq!(
+            check("what a NOOB!"),
+            Some(Violation::BannedWord("noob".to_string()))
+        );
+        assert_eq!(
+            check("just go, away"),
+            Some(Violation::BannedWord("go away".to_string()))
+        );
+        assert_eq!(check("noobs and snoobs"), None);
+        assert_eq!(check("go somewhere, away"), None);
+    }
+
+    #[test]
+    fn test_spam_detection() {
+        let mut moderator = Moderator::default();
+        let config = config();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        for _ in 0..config.max_repeats {
+            assert_eq!(moderator.check(&config, "Alyx", "gg", at(0)), None);
+        }
+
+        assert_eq!(
+            moderator.check(&config, "Alyx", "GG ", at(1)),
+            Some(Violation::Repeated)
+        );
+        assert_eq!(moderator.check(&config, "Alyx", "gg", at(2)), None);
+
+        // Other players and old messages are counted separately
+        assert_eq!(moderator.check(&config, "Barney", "gg", at(2)), None);
+
+        for i in 0..config.max_messages as u64 {
+            let message = format!("message {}", i);
+
+            assert_eq!(
+                moderator.check(&config, "Gordon", &message, at(100 + i)),
+                None
+            );
+        }
+
+        assert_eq!(
+            moderator.check(&config, "Gordon", "one more", at(106)),
+            Some(Violation::Flooding)
+        );
+        assert_eq!(
+            moderator.check(&config, "Gordon", "later", at(100 + config.spam_window * 2)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_quiet_players_are_forgotten() {
+        let mut moderator = Moderator::default();
+        let config = config();
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        for i in 0..100 {
+            moderator.check(&config, &format!("player {}", i), "hi", at(0));
+        }
+
+        assert_eq!(moderator.players.len(), 100);
+
+        moderator.check(&config, "Alyx", "hi", at(config.spam_window));
+
+        assert_eq!(moderator.players.keys().collect::<Vec<_>>(), vec!["Alyx"]);
+    }
+
+    #[test]
+    fn test_caps() {
+        assert!(is_shouting("WHY WOULD YOU DO THAT", 70, 8));
+        assert!(!is_shouting("Why would you do THAT", 70, 8));
+        assert!(!is_shouting("GG WP", 70, 8));
+        assert!(!is_shouting("WHY WOULD YOU DO THAT", 0, 8));
+    }
+
+    #[test]
+    fn test_action_command_strips_injection() {
+        assert_eq!(
+            action_command("kick \"{player}\"", "Bad\"; quit; \"Guy"),
+            "kick \"Bad quit Guy\""
+        );
+        assert_eq!(
+            warning("{player}, stop {reason}", "Alyx", &Violation::Caps),
+            "Alyx, stop excessive caps"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Mutex;
+
+use log::info;
+
+use crate::error::SourceCmdGuiResult;
+
+use super::Output;
+
+/// Keeps responses instead of sending them, for dry runs and tests
+#[derive(Default)]
+pub struct CaptureOutput {
+    messages: Mutex<Vec<String>>,
+}
+
+impl CaptureOutput {
+    /// Every message sent so far, oldest first
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Output for CaptureOutput {
+    async fn send(&self, message: &str) -> SourceCmdGuiResult {
+        info!("Captured response: {}", message);
+
+        self.messages.lock().unwrap().push(message.to_string());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_capture_keeps_messages_in_order() {
+        let output = CaptureOutput::default();
+
+        output.send("first").await.unwrap();
+        output.send("second").await.unwrap();
+
+        assert_eq!(output.messages(), vec!["first", "second"]);
+    }
+}
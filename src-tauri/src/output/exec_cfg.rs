@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use enigo::{Enigo, Key, KeyboardControllable};
+use tokio::{fs, sync::Mutex};
+
+use crate::{error::SourceCmdGuiResult, rcon::say_command};
+
+use super::Output;
+
+/// Writes each response as a `say` command into a cfg file the game executes,
+/// e.g. with `bind F8 "exec source_cmd"`
+pub struct ExecCfgOutput {
+    cfg_path: PathBuf,
+    /// Pressed after writing so the game executes the cfg, otherwise the user presses it
+    exec_key: Option<Key>,
+    /// Keeps a response from being overwritten before the game executed it
+    lock: Mutex<()>,
+}
+
+impl ExecCfgOutput {
+    pub fn new(cfg_path: PathBuf, exec_key: Option<Key>) -> Self {
+        Self {
+            cfg_path,
+            exec_key,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+/// Parses a key bound to `exec`, a single character or `F1` to `F12`
+///
+/// # Returns
+/// `None` if the key is empty or unknown
+pub fn parse_key(key: &str) -> Option<Key> {
+    let key = key.trim();
+    let mut chars = key.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => return Some(Key::Layout(ch.to_ascii_lowercase())),
+        (None, _) => return None,
+        _ => {}
+    }
+
+    Some(match key.to_ascii_uppercase().as_str() {
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        _ => return None,
+    })
+}
+
+#[async_trait::async_trait]
+impl Output for ExecCfgOutput {
+    async fn send(&self, message: &str) -> SourceCmdGuiResult {
+        let _guard = self.lock.lock().await;
+
+        fs::write(&self.cfg_path, format!("{}\n", say_command(message))).await?;
+
+        if let Some(exec_key) = self.exec_key {
+            tokio::task::spawn_blocking(move || Enigo::new().key_click(exec_key)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("K"), Some(Key::Layout('k')));
+        assert_eq!(parse_key("f8"), Some(Key::F8));
+        assert_eq!(parse_key(""), None);
+        assert_eq!(parse_key("F13"), None);
+    }
+}
@@ -0,0 +1,59 @@
+use std::{thread, time::Duration};
+
+use enigo::{Enigo, Key, KeyboardControllable};
+
+use crate::error::SourceCmdGuiResult;
+
+use super::Output;
+
+/// Gives the game time to open the chat box before typing into it
+const KEY_DELAY: Duration = Duration::from_millis(50);
+
+/// Types responses into the game chat, needs the game window focused
+pub struct KeystrokeOutput {
+    chat_key: Key,
+    whisper_command: Option<&'static str>,
+}
+
+impl KeystrokeOutput {
+    /// # Arguments
+    /// chat_key - The key that opens the chat box
+    /// whisper_command - The chat command replying privately, e.g. `/msg` in Minecraft
+    pub fn new(chat_key: Key, whisper_command: Option<&'static str>) -> Self {
+        Self {
+            chat_key,
+            whisper_command,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Output for KeystrokeOutput {
+    async fn send(&self, message: &str) -> SourceCmdGuiResult {
+        let chat_key = self.chat_key;
+        let message = message.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let mut enigo = Enigo::new();
+
+            enigo.key_click(chat_key);
+            thread::sleep(KEY_DELAY);
+            enigo.key_sequence(&message);
+            thread::sleep(KEY_DELAY);
+            enigo.key_click(Key::Return);
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn whisper(&self, player: &str, message: &str) -> SourceCmdGuiResult {
+        match self.whisper_command {
+            Some(command) => {
+                self.send(&format!("{} {} {}", command, player, message))
+                    .await
+            }
+            None => self.send_to(player, message).await,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::{error::SourceCmdGuiResult, model::MinecraftReply, rcon::RconClient};
+
+use super::Output;
+
+/// Replies through a Minecraft server's RCON, for bots reading the server's `logs/latest.log`
+pub struct MinecraftRconOutput {
+    client: Arc<RconClient>,
+    reply: MinecraftReply,
+}
+
+impl MinecraftRconOutput {
+    pub fn new(client: Arc<RconClient>, reply: MinecraftReply) -> Self {
+        Self { client, reply }
+    }
+
+    /// Builds the server command sending `message`
+    ///
+    /// # Arguments
+    /// reply - How to reply
+    /// player - Who ran the command, `None` for event responses
+    /// message - The response
+    fn command(reply: MinecraftReply, player: Option<&str>, message: &str) -> String {
+        // Commands end at a line break
+        let message = message.replace(['\r', '\n'], " ");
+
+        match (reply, player) {
+            (MinecraftReply::PrivateMessage, Some(player)) => format!("msg {} {}", player, message),
+            (MinecraftReply::Say, _) => format!("say {}", message),
+            _ => format!("tellraw @a {}", json!({ "text": message })),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Output for MinecraftRconOutput {
+    async fn send(&self, message: &str) -> SourceCmdGuiResult {
+        self.client
+            .exec(&Self::command(self.reply, None, message))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn send_to(&self, player: &str, message: &str) -> SourceCmdGuiResult {
+        self.client
+            .exec(&Self::command(self.reply, Some(player), message))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Always answers with `msg`, whatever the reply setting, so whispers stay private
+    async fn whisper(&self, player: &str, message: &str) -> SourceCmdGuiResult {
+        self.client
+            .exec(&Self::command(
+                MinecraftReply::PrivateMessage,
+                Some(player),
+                message,
+            ))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rcon::test_server::{Flavor, TestServer, PASSWORD};
+
+    use super::*;
+
+    async fn output(server: &TestServer, reply: MinecraftReply) -> MinecraftRconOutput {
+        MinecraftRconOutput::new(Arc::new(RconClient::new(&server.address, PASSWORD)), reply)
+    }
+
+    #[tokio::test]
+    async fn test_replies() {
+        let server = TestServer::start(Flavor::Minecraft, false).await;
+
+        let tellraw = output(&server, MinecraftReply::Tellraw).await;
+        tellraw.send_to("Steve", "1 + 1 = \"2\"").await.unwrap();
+
+        let say = output(&server, MinecraftReply::Say).await;
+        say.send("line\nbreak").await.unwrap();
+
+        let private = output(&server, MinecraftReply::PrivateMessage).await;
+        private.send_to("Steve", "pong").await.unwrap();
+        private.send("Welcome, Alex!").await.unwrap();
+
+        // Whispers are answered privately even when replies go to everyone
+        tellraw.whisper("Alex", "secret").await.unwrap();
+
+        assert_eq!(
+            server.commands(),
+            vec![
+                r#"tellraw @a {"text":"1 + 1 = \"2\""}"#,
+                "say line break",
+                "msg Steve pong",
+                r#"tellraw @a {"text":"Welcome, Alex!"}"#,
+                "msg Alex secret",
+            ]
+        );
+    }
+}
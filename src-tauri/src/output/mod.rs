@@ -0,0 +1,80 @@
+mod capture;
+mod exec_cfg;
+mod keystrokes;
+mod minecraft;
+mod queue;
+mod rcon;
+
+use std::{path::PathBuf, sync::Arc};
+
+pub use capture::CaptureOutput;
+pub use exec_cfg::{parse_key, ExecCfgOutput};
+pub use keystrokes::KeystrokeOutput;
+pub use minecraft::MinecraftRconOutput;
+pub use queue::{OutgoingQueue, Priority, QueuedMessage};
+pub use rcon::RconOutput;
+
+use crate::{
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    model::{state::Config, OutputKind},
+    rcon::RconClient,
+};
+
+/// Delivers command and event responses to the game
+#[async_trait::async_trait]
+pub trait Output: Send + Sync {
+    /// # Arguments
+    /// message - The response, already cut to the game's chat length
+    async fn send(&self, message: &str) -> SourceCmdGuiResult;
+
+    /// Replies to the player who ran a command, backends that can't whisper send it to everyone
+    ///
+    /// # Arguments
+    /// player - The player's name
+    /// message - The response, already cut to the game's chat length
+    async fn send_to(&self, _player: &str, message: &str) -> SourceCmdGuiResult {
+        self.send(message).await
+    }
+
+    /// Replies privately to a player who whispered the command, backends that can't
+    /// whisper reply like `send_to`
+    ///
+    /// # Arguments
+    /// player - The player's name
+    /// message - The response, already cut to the game's chat length
+    async fn whisper(&self, player: &str, message: &str) -> SourceCmdGuiResult {
+        self.send_to(player, message).await
+    }
+}
+
+pub type SharedOutput = Arc<dyn Output>;
+
+/// Builds the output backend selected in the config
+///
+/// # Arguments
+/// config - The config the parser is started with
+/// rcon - The server's RCON client, if a password is stored for it
+pub fn build(config: &Config, rcon: Option<Arc<RconClient>>) -> SourceCmdGuiResult<SharedOutput> {
+    let output = &config.output;
+
+    Ok(match output.kind {
+        OutputKind::Keystrokes => Arc::new(KeystrokeOutput::new(
+            config.parser.get_chat_key(&config.custom_parser),
+            config.parser.get_whisper_command(),
+        )),
+        OutputKind::ExecCfg => Arc::new(ExecCfgOutput::new(
+            PathBuf::from(&output.exec_cfg_path),
+            parse_key(&output.exec_key),
+        )),
+        OutputKind::Rcon => Arc::new(RconOutput::new(rcon.ok_or_else(no_rcon_password)?)),
+        OutputKind::MinecraftRcon => Arc::new(MinecraftRconOutput::new(
+            rcon.ok_or_else(no_rcon_password)?,
+            output.minecraft_reply,
+        )),
+        OutputKind::Capture => Arc::new(CaptureOutput::default()),
+    })
+}
+
+fn no_rcon_password() -> SourceCmdGuiError {
+    SourceCmdGuiError::RconError("No RCON password is stored".to_string())
+}
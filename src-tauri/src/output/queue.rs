@@ -0,0 +1,346 @@
+use std::{
+    cmp::Reverse,
+    collections::VecDeque,
+    convert::Infallible,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use log::{debug, error, warn};
+use serde::Serialize;
+use tokio::sync::{mpsc, Notify};
+
+use super::SharedOutput;
+
+/// The same response to the same player is only sent once in this window
+const DEDUP_WINDOW: Duration = Duration::from_secs(5);
+
+/// Responses waiting beyond this are dropped, least important first
+const MAX_QUEUE_LEN: usize = 20;
+
+/// Chat lines matching a response sent this recently are the bot's own output
+const ECHO_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Priority {
+    /// Global commands and game events
+    Low,
+    /// Commands run by other players
+    Normal,
+    /// Commands run by the owner
+    High,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueuedMessage {
+    pub id: u64,
+    pub message: String,
+    /// Who ran the command, `None` for responses to everyone
+    pub player: Option<String>,
+    /// The player whispered the command, so the response is whispered back
+    pub whisper: bool,
+    pub priority: Priority,
+    pub queued_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: Vec<QueuedMessage>,
+    /// Recently queued responses and their players, for dropping duplicates
+    recent: VecDeque<(Option<String>, String, Instant)>,
+    /// Responses sent, for recognising them when they show up in the log
+    sent: VecDeque<(String, Instant)>,
+    next_id: u64,
+}
+
+/// Sends responses one at a time, so commands firing on the same line don't type over each other
+pub struct OutgoingQueue {
+    state: Mutex<QueueState>,
+    notify: Notify,
+    /// Receives the pending responses on every change, forwarded to the frontend
+    sender: Option<mpsc::Sender<Vec<QueuedMessage>>>,
+}
+
+impl OutgoingQueue {
+    pub fn new(sender: Option<mpsc::Sender<Vec<QueuedMessage>>>) -> Self {
+        Self {
+            state: Mutex::default(),
+            notify: Notify::new(),
+            sender,
+        }
+    }
+
+    /// # Arguments
+    /// message - The response, already cut to the game's chat length
+    /// player - Who ran the command, to reply to them privately where the output supports it
+    /// priority - Higher priorities are sent first, in the order they were queued
+    ///
+    /// # Returns
+    /// Whether the response was queued, `false` for duplicates
+    pub fn push(&self, message: String, player: Option<&str>, priority: Priority) -> bool {
+        self.enqueue(message, player, false, priority)
+    }
+
+    /// Queues the response to a whispered command, it is whispered back instead of sent to chat
+    ///
+    /// # Returns
+    /// Whether the response was queued, `false` for duplicates
+    pub fn push_whisper(&self, message: String, player: &str, priority: Priority) -> bool {
+        self.enqueue(message, Some(player), true, priority)
+    }
+
+    fn enqueue(
+        &self,
+        message: String,
+        player: Option<&str>,
+        whisper: bool,
+        priority: Priority,
+    ) -> bool {
+        let now = Instant::now();
+        let player = player.map(str::to_string);
+
+        {
+            let mut state = self.state.lock().unwrap();
+
+            state
+                .recent
+                .retain(|(_, _, queued)| now.duration_since(*queued) < DEDUP_WINDOW);
+
+            if state
+                .recent
+                .iter()
+                .any(|(recent_player, recent, _)| *recent_player == player && *recent == message)
+            {
+                debug!("Dropping duplicate response: {}", message);
+                return false;
+            }
+
+            state
+                .recent
+                .push_back((player.clone(), message.clone(), now));
+
+            let id = state.next_id;
+            state.next_id += 1;
+
+            state.pending.push(QueuedMessage {
+                id,
+                message,
+                player,
+                whisper,
+                priority,
+                queued_at: Utc::now(),
+            });
+
+            if state.pending.len() > MAX_QUEUE_LEN {
+                // The newest of the least important responses
+                if let Some(index) = state
+                    .pending
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(index, queued)| (queued.priority, Reverse(*index)))
+                    .map(|(index, _)| index)
+                {
+                    let dropped = state.pending.remove(index);
+                    warn!("Response queue is full, dropping: {}", dropped.message);
+                }
+            }
+        }
+
+        self.notify.notify_one();
+        self.publish();
+
+        true
+    }
+
+    /// The responses waiting to be sent, in the order they will be sent
+    pub fn pending(&self) -> Vec<QueuedMessage> {
+        let mut pending = self.state.lock().unwrap().pending.clone();
+        pending.sort_by_key(|queued| (Reverse(queued.priority), queued.id));
+
+        pending
+    }
+
+    /// Drops every response waiting to be sent
+    pub fn clear(&self) {
+        self.state.lock().unwrap().pending.clear();
+        self.publish();
+    }
+
+    /// Whether a chat line is a response sent in the last `ECHO_WINDOW`
+    pub fn was_sent_recently(&self, line: &str) -> bool {
+        let now = Instant::now();
+        let line = line.trim();
+
+        self.state
+            .lock()
+            .unwrap()
+            .sent
+            .iter()
+            .any(|(sent, at)| now.duration_since(*at) < ECHO_WINDOW && sent.trim() == line)
+    }
+
+    /// Takes the most important response, the oldest one among equals, and remembers it as sent
+    fn pop(&self) -> Option<QueuedMessage> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        let index = state
+            .pending
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, queued)| (queued.priority, Reverse(*index)))
+            .map(|(index, _)| index)?;
+
+        let queued = state.pending.remove(index);
+
+        state
+            .sent
+            .retain(|(_, at)| now.duration_since(*at) < ECHO_WINDOW);
+        state.sent.push_back((queued.message.clone(), now));
+
+        Some(queued)
+    }
+
+    fn publish(&self) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.try_send(self.pending());
+        }
+    }
+
+    /// Sends queued responses until the future is dropped, failures are logged and skipped
+    ///
+    /// # Arguments
+    /// output - Where responses are sent
+    pub async fn run(&self, output: SharedOutput) -> Infallible {
+        loop {
+            let Some(queued) = self.pop() else {
+                self.notify.notified().await;
+                continue;
+            };
+
+            self.publish();
+
+            let result = match (&queued.player, queued.whisper) {
+                (Some(player), true) => output.whisper(player, &queued.message).await,
+                (Some(player), false) => output.send_to(player, &queued.message).await,
+                (None, _) => output.send(&queued.message).await,
+            };
+
+            if let Err(e) = result {
+                error!("Failed to send response \"{}\": {}", queued.message, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::output::CaptureOutput;
+
+    use super::*;
+
+    fn messages(queue: &OutgoingQueue) -> Vec<String> {
+        queue
+            .pending()
+            .into_iter()
+            .map(|queued| queued.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_priority_order_and_duplicates() {
+        let queue = OutgoingQueue::new(None);
+
+        assert!(queue.push("mimic".to_string(), Some("Alyx"), Priority::Low));
+        assert!(queue.push("pong".to_string(), Some("Alyx"), Priority::Normal));
+        assert!(queue.push("owner".to_string(), Some("Gordon"), Priority::High));
+        assert!(queue.push("pong 2".to_string(), Some("Barney"), Priority::Normal));
+
+        assert!(!queue.push("pong".to_string(), Some("Alyx"), Priority::Normal));
+        assert!(queue.push("pong".to_string(), Some("Barney"), Priority::Normal));
+
+        assert_eq!(
+            messages(&queue),
+            vec!["owner", "pong", "pong 2", "pong", "mimic"]
+        );
+        assert_eq!(queue.pop().unwrap().message, "owner");
+        assert!(queue.was_sent_recently(" owner "));
+        assert!(!queue.was_sent_recently("pong"));
+
+        queue.clear();
+        assert!(queue.pending().is_empty());
+    }
+
+    #[test]
+    fn test_whispers_are_marked() {
+        let queue = OutgoingQueue::new(None);
+
+        assert!(queue.push_whisper("secret".to_string(), "Alyx", Priority::Normal));
+        assert!(!queue.push("secret".to_string(), Some("Alyx"), Priority::Normal));
+        assert!(queue.push("public".to_string(), Some("Alyx"), Priority::Normal));
+
+        let pending: Vec<(String, bool)> = queue
+            .pending()
+            .into_iter()
+            .map(|queued| (queued.message, queued.whisper))
+            .collect();
+
+        assert_eq!(
+            pending,
+            vec![("secret".to_string(), true), ("public".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_full_queue_drops_least_important() {
+        let queue = OutgoingQueue::new(None);
+
+        for i in 0..MAX_QUEUE_LEN {
+            queue.push(format!("low {}", i), None, Priority::Low);
+        }
+
+        queue.push("high".to_string(), None, Priority::High);
+        queue.push("low extra".to_string(), None, Priority::Low);
+
+        let pending = messages(&queue);
+
+        assert_eq!(pending.len(), MAX_QUEUE_LEN);
+        assert_eq!(pending[0], "high");
+        assert_eq!(pending[1], "low 0");
+        assert!(!pending.contains(&"low extra".to_string()));
+        assert!(!pending.contains(&format!("low {}", MAX_QUEUE_LEN - 1)));
+    }
+
+    #[tokio::test]
+    async fn test_run_sends_in_order() {
+        let queue = Arc::new(OutgoingQueue::new(None));
+        let output = Arc::new(CaptureOutput::default());
+
+        queue.push("event".to_string(), None, Priority::Low);
+        queue.push("command".to_string(), Some("Alyx"), Priority::Normal);
+
+        let sending = tokio::spawn({
+            let queue = queue.clone();
+            let output: SharedOutput = output.clone();
+
+            async move { queue.run(output).await }
+        });
+
+        queue.push("later".to_string(), None, Priority::Low);
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while output.messages().len() < 3 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        sending.abort();
+
+        assert_eq!(output.messages(), vec!["command", "event", "later"]);
+    }
+}
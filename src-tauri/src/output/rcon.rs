@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::{error::SourceCmdGuiResult, rcon::RconClient};
+
+use super::Output;
+
+/// Sends responses with `say` over the server's remote console, works without the game focused
+pub struct RconOutput {
+    client: Arc<RconClient>,
+}
+
+impl RconOutput {
+    pub fn new(client: Arc<RconClient>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl Output for RconOutput {
+    async fn send(&self, message: &str) -> SourceCmdGuiResult {
+        // Responses repeat what players typed, so they must not be able to run other commands
+        self.client.say(message).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rcon::test_server::{Flavor, TestServer, PASSWORD};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_say_cannot_run_other_commands() {
+        let server = TestServer::start(Flavor::Source, false).await;
+        let output = RconOutput::new(Arc::new(RconClient::new(&server.address, PASSWORD)));
+
+        output.send("hi\"; rcon_password x\nquit").await.unwrap();
+        output.send_to("Gordon", "pong").await.unwrap();
+
+        assert_eq!(
+            server.commands(),
+            vec!["say \"hi', rcon_password x quit\"", "say \"pong\""]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use regex::Regex;
+use serde::Serialize;
+use source_cmd_parser::{log_parser::ParseLog, model::ChatMessage};
+
+use crate::{
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    identity::PlayerIds,
+};
+
+/// Named groups every pattern has to define
+const REQUIRED_GROUPS: [&str; 2] = ["user", "message"];
+
+/// What a pattern captured from a single chat line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapturedLine {
+    pub user: String,
+    pub message: String,
+    pub channel: Option<String>,
+    pub team: Option<String>,
+    /// Whether the `dead` group matched anything, e.g. a `*DEAD*` prefix
+    pub dead: bool,
+    pub steam_id: Option<String>,
+}
+
+/// The result of testing a pattern against one sample line
+#[derive(Clone, Serialize)]
+pub struct PatternMatch {
+    pub line: String,
+    pub captured: Option<CapturedLine>,
+}
+
+/// A log parser driven by a user supplied regex
+///
+/// The pattern must have `user` and `message` named groups and may have
+/// `channel`, `team`, `dead` and `steam_id` groups.
+pub struct CustomParser {
+    regex: Regex,
+    /// Where captured Steam IDs are recorded
+    player_ids: PlayerIds,
+    /// Captured users that are console output rather than players, e.g. `Unknown command`
+    ignored_users: &'static [&'static str],
+}
+
+impl CustomParser {
+    pub fn new(pattern: &str) -> SourceCmdGuiResult<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| SourceCmdGuiError::InvalidParserPattern(e.to_string()))?;
+
+        let missing: Vec<&str> = REQUIRED_GROUPS
+            .into_iter()
+            .filter(|group| !regex.capture_names().flatten().any(|name| name == *group))
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(SourceCmdGuiError::InvalidParserPattern(format!(
+                "Missing named groups: {}",
+                missing.join(", ")
+            )));
+        }
+
+        Ok(Self {
+            regex,
+            player_ids: PlayerIds::default(),
+            ignored_users: &[],
+        })
+    }
+
+    /// Records the Steam ID of every chat line's sender in `player_ids`
+    pub fn with_player_ids(mut self, player_ids: PlayerIds) -> Self {
+        self.player_ids = player_ids;
+        self
+    }
+
+    /// Skips lines whose captured user is one of `users`, for console output shaped like chat
+    pub fn ignoring_users(mut self, users: &'static [&'static str]) -> Self {
+        self.ignored_users = users;
+        self
+    }
+
+    pub fn capture(&self, line: &str) -> Option<CapturedLine> {
+        let captures = self.regex.captures(line)?;
+        let group = |name: &str| {
+            captures
+                .name(name)
+                .map(|value| value.as_str().trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let user = group("user")?;
+
+        if self.ignored_users.contains(&user.as_str()) {
+            return None;
+        }
+
+        Some(CapturedLine {
+            user,
+            message: group("message")?,
+            channel: group("channel"),
+            team: group("team"),
+            dead: group("dead").is_some(),
+            steam_id: group("steam_id"),
+        })
+    }
+
+    /// Runs the pattern over sample lines to show what it captures
+    pub fn test(&self, lines: &[String]) -> Vec<PatternMatch> {
+        lines
+            .iter()
+            .map(|line| PatternMatch {
+                line: line.clone(),
+                captured: self.capture(line),
+            })
+            .collect()
+    }
+}
+
+impl ParseLog for CustomParser {
+    fn parse_command(&self, line: &str) -> Option<ChatMessage> {
+        let captured = self.capture(line)?;
+
+        if let Some(steam_id) = &captured.steam_id {
+            self.player_ids.record(&captured.user, steam_id);
+        }
+
+        Some(super::to_chat_message(captured.user, captured.message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE_PATTERN: &str = r"^(?P<dead>\*DEAD\*)?\s*(?:\((?P<team>[^)]+)\)\s*)?(?P<user>.+?)\x{200e}? : (?P<message>.*)$";
+
+    #[test]
+    fn test_capture() {
+        let parser = CustomParser::new(SOURCE_PATTERN).unwrap();
+
+        assert_eq!(
+            parser.capture("*DEAD*(Counter-Terrorist) Player : .ping now"),
+            Some(CapturedLine {
+                user: "Player".to_string(),
+                message: ".ping now".to_string(),
+                channel: None,
+                team: Some("Counter-Terrorist".to_string()),
+                dead: true,
+                steam_id: None,
+            })
+        );
+        assert_eq!(parser.capture("Player : ").map(|line| line.user), None);
+        assert_eq!(parser.capture("Connected to server"), None);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let parser = CustomParser::new(SOURCE_PATTERN).unwrap();
+        let message = parser.parse_command("Player : .ping  now").unwrap();
+
+        assert_eq!(message.user_name, "Player");
+        assert_eq!(message.command, ".ping");
+        assert_eq!(message.message, "now");
+        assert_eq!(message.raw_message, ".ping  now");
+    }
+
+    #[test]
+    fn test_records_steam_ids() {
+        let player_ids = PlayerIds::default();
+        let parser = CustomParser::new(
+            r#"^"(?P<user>.+?)<\d+><(?P<steam_id>[^>]+)><(?P<team>[^>]*)>" say(?:_team)? "(?P<message>.*)"$"#,
+        )
+        .unwrap()
+        .with_player_ids(player_ids.clone());
+
+        let message = parser
+            .parse_command(r#""Gordon<2><STEAM_1:0:1234><CT>" say ".ping""#)
+            .unwrap();
+
+        assert_eq!(message.user_name, "Gordon");
+        assert_eq!(player_ids.get("Gordon"), Some("STEAM_1:0:1234".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(matches!(
+            CustomParser::new("(?P<user>"),
+            Err(SourceCmdGuiError::InvalidParserPattern(_))
+        ));
+        assert!(matches!(
+            CustomParser::new("(?P<user>.+): (?P<text>.+)"),
+            Err(SourceCmdGuiError::InvalidParserPattern(e)) if e.contains("message")
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+use regex::{Captures, Regex};
+use serde::Serialize;
+use source_cmd_parser::log_parser::ParseLog;
+
+use crate::model::GameParser;
+
+/// Prefix of HLDS style log lines, e.g. `L 03/14/2024 - 20:15:02: `
+const LOG_TIMESTAMP: &str = r"^L \d{2}/\d{2}/\d{4} - \d{2}:\d{2}:\d{2}: ";
+
+/// A player in an HLDS style log line, e.g. `"Player<2><STEAM_1:0:1234><CT>"`
+const LOG_PLAYER: &str = r#""(?P<{}>.+?)<\d+><[^>]*><[^>]*>""#;
+
+/// Something that happened in game, other than chat
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    Kill {
+        killer: String,
+        victim: String,
+        weapon: Option<String>,
+        headshot: bool,
+    },
+    PlayerConnected {
+        player: String,
+    },
+    PlayerDisconnected {
+        player: String,
+        reason: Option<String>,
+    },
+    RoundStart,
+    RoundEnd {
+        winner: Option<String>,
+    },
+    MapChange {
+        map: String,
+    },
+}
+
+impl GameEvent {
+    /// The script trigger subscribing to this kind of event, e.g. `event:kill`
+    pub fn trigger(&self) -> &'static str {
+        match self {
+            GameEvent::Kill { .. } => "event:kill",
+            GameEvent::PlayerConnected { .. } => "event:player_connected",
+            GameEvent::PlayerDisconnected { .. } => "event:player_disconnected",
+            GameEvent::RoundStart => "event:round_start",
+            GameEvent::RoundEnd { .. } => "event:round_end",
+            GameEvent::MapChange { .. } => "event:map_change",
+        }
+    }
+}
+
+type EventBuilder = fn(&Captures) -> Option<GameEvent>;
+
+/// Parses kill feed, connect and round lines from a game's log
+pub struct EventParser {
+    /// Part of every line that is stripped before matching, e.g. a timestamp
+    prefix: Option<Regex>,
+    patterns: Vec<(Regex, EventBuilder)>,
+    /// The game's chat parser, lines it accepts are chat and never events
+    chat: Option<Box<dyn ParseLog>>,
+}
+
+impl EventParser {
+    /// # Arguments
+    /// parser - The game the log belongs to, custom parsers have no events
+    pub fn new(parser: &GameParser) -> Self {
+        match parser {
+            GameParser::Minecraft => Self::minecraft(),
+            GameParser::Custom => Self {
+                prefix: None,
+                patterns: vec![],
+                chat: None,
+            },
+            _ => Self::source(),
+        }
+    }
+
+    /// Skips the lines `chat` reads as chat, so a message like "a killed b with c." isn't a kill
+    pub fn with_chat(mut self, chat: Box<dyn ParseLog>) -> Self {
+        self.chat = Some(chat);
+        self
+    }
+
+    fn source() -> Self {
+        let player = |group: &str| LOG_PLAYER.replace("{}", group);
+
+        let patterns: Vec<(String, EventBuilder)> = vec![
+            // Console output
+            (
+                r"^(?P<killer>.+?) killed (?P<victim>.+?) with (?P<weapon>[\w-]+)\.(?P<flags>.*)$"
+                    .to_string(),
+                kill,
+            ),
+            (
+                r"^(?P<player>.+?) (?:connected|has joined the game)\.?$".to_string(),
+                player_connected,
+            ),
+            (
+                r"^Dropped (?P<player>.+?) from server(?: \((?P<reason>.+)\))?$".to_string(),
+                player_disconnected,
+            ),
+            (r"^Map: (?P<map>\S+)$".to_string(), map_change),
+            // Server logs
+            (
+                format!(
+                    r#"^{} killed {} with "(?P<weapon>[^"]+)"(?P<flags>.*)$"#,
+                    player("killer"),
+                    player("victim")
+                ),
+                kill,
+            ),
+            (
+                format!(r"^{} entered the game$", player("player")),
+                player_connected,
+            ),
+            (
+                format!(
+                    r#"^{} disconnected(?: \(reason "(?P<reason>[^"]*)"\))?$"#,
+                    player("player")
+                ),
+                player_disconnected,
+            ),
+            (r#"^World triggered "Round_Start"$"#.to_string(), |_| {
+                Some(GameEvent::RoundStart)
+            }),
+            (r#"^World triggered "Round_Draw"$"#.to_string(), |_| {
+                Some(GameEvent::RoundEnd { winner: None })
+            }),
+            (
+                r#"^Team "(?P<team>[^"]+)" triggered "[^"]*Win[^"]*""#.to_string(),
+                |captures| {
+                    Some(GameEvent::RoundEnd {
+                        winner: Some(captures["team"].to_string()),
+                    })
+                },
+            ),
+            (r#"^Started map "(?P<map>[^"]+)""#.to_string(), map_change),
+        ];
+
+        Self {
+            prefix: Some(Regex::new(LOG_TIMESTAMP).unwrap()),
+            patterns: patterns
+                .into_iter()
+                .map(|(pattern, builder)| (Regex::new(&pattern).unwrap(), builder))
+                .collect(),
+            chat: None,
+        }
+    }
+
+    fn minecraft() -> Self {
+        Self {
+            // Client lines are marked as chat, server lines only have the thread prefix
+            prefix: Some(Regex::new(r"^.*?\]: (?:\[System\] )?(?:\[CHAT\] )?").unwrap()),
+            patterns: vec![
+                (
+                    Regex::new(r"^(?P<player>\w{1,16}) joined the game$").unwrap(),
+                    player_connected,
+                ),
+                (
+                    Regex::new(r"^(?P<player>\w{1,16}) left the game$").unwrap(),
+                    player_disconnected,
+                ),
+                (
+                    Regex::new(
+                        r"^(?P<victim>\w{1,16}) was (?:slain|shot) by (?P<killer>\w{1,16})(?: using \[(?P<weapon>.+)\])?$",
+                    )
+                    .unwrap(),
+                    kill,
+                ),
+            ],
+            chat: None,
+        }
+    }
+
+    pub fn parse(&self, line: &str) -> Option<GameEvent> {
+        let line = line.trim();
+
+        // Chat is handled by the log parser
+        if self
+            .chat
+            .as_ref()
+            .is_some_and(|chat| chat.parse_command(line).is_some())
+        {
+            return None;
+        }
+
+        let line = match &self.prefix {
+            Some(prefix) => prefix
+                .find(line)
+                .map_or(line, |prefix| &line[prefix.end()..]),
+            None => line,
+        };
+
+        self.patterns.iter().find_map(|(regex, builder)| {
+            regex.captures(line).and_then(|captures| builder(&captures))
+        })
+    }
+}
+
+fn kill(captures: &Captures) -> Option<GameEvent> {
+    Some(GameEvent::Kill {
+        killer: captures["killer"].to_string(),
+        victim: captures["victim"].to_string(),
+        weapon: captures
+            .name("weapon")
+            .map(|weapon| weapon.as_str().to_string()),
+        headshot: captures
+            .name("flags")
+            .is_some_and(|flags| flags.as_str().contains("headshot")),
+    })
+}
+
+fn player_connected(captures: &Captures) -> Option<GameEvent> {
+    Some(GameEvent::PlayerConnected {
+        player: captures["player"].to_string(),
+    })
+}
+
+fn player_disconnected(captures: &Captures) -> Option<GameEvent> {
+    Some(GameEvent::PlayerDisconnected {
+        player: captures["player"].to_string(),
+        reason: captures
+            .name("reason")
+            .map(|reason| reason.as_str().to_string()),
+    })
+}
+
+fn map_change(captures: &Captures) -> Option<GameEvent> {
+    Some(GameEvent::MapChange {
+        map: captures["map"].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::{gmod_parser, source_parser};
+
+    fn kill_event(killer: &str, victim: &str, weapon: &str, headshot: bool) -> Option<GameEvent> {
+        Some(GameEvent::Kill {
+            killer: killer.to_string(),
+            victim: victim.to_string(),
+            weapon: Some(weapon.to_string()),
+            headshot,
+        })
+    }
+
+    #[test]
+    fn test_source_console_events() {
+        let parser =
+            EventParser::new(&GameParser::TeamFortress2).with_chat(Box::new(source_parser()));
+
+        assert_eq!(
+            parser.parse("Heavy Weapons Guy killed scout main with minigun."),
+            kill_event("Heavy Weapons Guy", "scout main", "minigun", false)
+        );
+        assert_eq!(
+            parser.parse("Player killed Other with deagle. (headshot)"),
+            kill_event("Player", "Other", "deagle", true)
+        );
+        assert_eq!(
+            parser.parse("Gordon connected."),
+            Some(GameEvent::PlayerConnected {
+                player: "Gordon".to_string()
+            })
+        );
+        assert_eq!(
+            parser.parse("Dropped Gordon from server (Disconnect by user.)"),
+            Some(GameEvent::PlayerDisconnected {
+                player: "Gordon".to_string(),
+                reason: Some("Disconnect by user.".to_string())
+            })
+        );
+        assert_eq!(
+            parser.parse("Map: ctf_2fort"),
+            Some(GameEvent::MapChange {
+                map: "ctf_2fort".to_string()
+            })
+        );
+        assert_eq!(parser.parse("Player :  bob killed alice with knife."), None);
+        assert_eq!(parser.parse("*DEAD* Gordon :  I just connected."), None);
+    }
+
+    #[test]
+    fn test_counter_strike_2_chat_is_not_an_event() {
+        let parser = EventParser::new(&GameParser::CounterStrike2)
+            .with_chat(Box::<source_cmd_parser::parsers::Cs2LogParser>::default());
+
+        assert_eq!(
+            parser.parse("10/18 20:15:02  [ALL] Gordon\u{200e}: I just connected"),
+            None
+        );
+        assert_eq!(
+            parser.parse("10/18 20:15:02  [CT] Gordon\u{200e}: a killed b with c."),
+            None
+        );
+        assert_eq!(
+            parser.parse("Gordon killed Alyx with ak47."),
+            kill_event("Gordon", "Alyx", "ak47", false)
+        );
+    }
+
+    #[test]
+    fn test_garrys_mod_chat_is_not_an_event() {
+        let parser = EventParser::new(&GameParser::GarrysMod).with_chat(Box::new(gmod_parser()));
+
+        assert_eq!(parser.parse("Gordon: a killed b with c."), None);
+        assert_eq!(parser.parse("*DEAD* Gordon: I just connected."), None);
+        assert_eq!(
+            parser.parse("(TEAM) Gordon: Dropped Alyx from server"),
+            None
+        );
+        assert_eq!(
+            parser.parse("a killed b with c."),
+            kill_event("a", "b", "c", false)
+        );
+        assert_eq!(
+            parser.parse("Gordon connected."),
+            Some(GameEvent::PlayerConnected {
+                player: "Gordon".to_string()
+            })
+        );
+        assert_eq!(
+            parser.parse("Map: gm_construct"),
+            Some(GameEvent::MapChange {
+                map: "gm_construct".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_server_log_events() {
+        let parser = EventParser::new(&GameParser::CounterStrikeSource);
+
+        assert_eq!(
+            parser.parse(
+                r#"L 03/14/2024 - 20:15:02: "Player<2><STEAM_1:0:1234><CT>" killed "Other<3><STEAM_1:1:5678><TERRORIST>" with "ak47" (headshot)"#
+            ),
+            kill_event("Player", "Other", "ak47", true)
+        );
+        assert_eq!(
+            parser.parse(
+                r#"L 03/14/2024 - 20:15:02: "Player<2><STEAM_1:0:1234><>" entered the game"#
+            ),
+            Some(GameEvent::PlayerConnected {
+                player: "Player".to_string()
+            })
+        );
+        assert_eq!(
+            parser.parse(r#"L 03/14/2024 - 20:15:02: World triggered "Round_Start""#),
+            Some(GameEvent::RoundStart)
+        );
+        assert_eq!(
+            parser.parse(
+                r#"L 03/14/2024 - 20:15:02: Team "CT" triggered "CTs_Win" (CT "3") (T "1")"#
+            ),
+            Some(GameEvent::RoundEnd {
+                winner: Some("CT".to_string())
+            })
+        );
+        assert_eq!(
+            parser.parse(r#"L 03/14/2024 - 20:15:02: World triggered "Round_End""#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_minecraft_events() {
+        let parser = EventParser::new(&GameParser::Minecraft);
+
+        assert_eq!(
+            parser.parse("[18:02:11] [Render thread/INFO]: [System] [CHAT] Steve joined the game"),
+            Some(GameEvent::PlayerConnected {
+                player: "Steve".to_string()
+            })
+        );
+        assert_eq!(
+            parser.parse(
+                "[18:02:11] [Server thread/INFO]: Alex was slain by Steve using [Excalibur]"
+            ),
+            kill_event("Steve", "Alex", "Excalibur", false)
+        );
+        assert_eq!(
+            parser.parse("[18:02:11] [Render thread/INFO]: [CHAT] <Steve> Alex left the game"),
+            None
+        );
+        assert_eq!(
+            EventParser::new(&GameParser::Custom).parse("Steve joined the game"),
+            None
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use regex::Regex;
+use source_cmd_parser::{log_parser::ParseLog, model::ChatMessage};
+
+/// Marks chat in the client log, e.g. `[12:00:00] [Render thread/INFO]: [System] [CHAT] <Steve> hi`
+const CHAT_MARKER: &str = "[CHAT] ";
+
+/// Ends the thread/level prefix of every log line, e.g. `[12:00:00] [Server thread/INFO]: `
+const LOG_PREFIX_END: &str = "]: ";
+
+/// Marks unsigned chat in the server log since 1.19.1, e.g. `[Not Secure] <Steve> hi`
+const NOT_SECURE: &str = "[Not Secure] ";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinecraftChat {
+    pub user: String,
+    pub message: String,
+    /// Sent privately with `/msg`, `/tell` or `/w`
+    pub whisper: bool,
+}
+
+/// Whether each player's last chat line was a whisper, so replies to whispers stay private.
+/// `ChatMessage` has no room for it, so the parser records it here for the runtime.
+#[derive(Clone, Default)]
+pub struct Whispers {
+    last_line: Arc<Mutex<HashMap<String, (String, bool)>>>,
+}
+
+impl Whispers {
+    fn record(&self, user: &str, raw_message: &str, whisper: bool) {
+        self.last_line
+            .lock()
+            .unwrap()
+            .insert(user.to_string(), (raw_message.to_string(), whisper));
+    }
+
+    /// Whether a message was whispered, checked as soon as the parser hands it over
+    ///
+    /// # Arguments
+    /// user - Who sent the message
+    /// raw_message - Everything they typed
+    pub fn is_whisper(&self, user: &str, raw_message: &str) -> bool {
+        self.last_line
+            .lock()
+            .unwrap()
+            .get(user)
+            .is_some_and(|(message, whisper)| *whisper && message == raw_message)
+    }
+}
+
+pub struct MinecraftParser {
+    /// `<Steve> hi`, the vanilla format, also used for ranks inside the brackets
+    vanilla: Regex,
+    /// `Steve whispers to you: hi` or Essentials' `[Steve -> me] hi`
+    whisper: Regex,
+    /// `You whisper to Steve: hi` or `[me -> Steve] hi`
+    outgoing_whisper: Regex,
+    /// `[VIP] Steve: hi` or `Admin Steve » hi`, as formatted by server chat plugins
+    ranked: Regex,
+    /// Where whispers are recorded
+    whispers: Whispers,
+}
+
+impl MinecraftParser {
+    pub fn new() -> Self {
+        Self {
+            vanilla: Regex::new(r"^<(?:[^>]*[\s\]])?(?P<user>\w{1,16})> (?P<message>.*)$").unwrap(),
+            whisper: Regex::new(
+                r"^(?:(?P<user>\w{1,16}) whispers(?: to you)?: |\[(?P<essentials>\w{1,16}) -> me\] )(?P<message>.*)$",
+            )
+            .unwrap(),
+            outgoing_whisper: Regex::new(r"^(?:You whisper to \w+: |\[me -> \w+\] )").unwrap(),
+            ranked: Regex::new(r"^(?:.*?[\s\]])??(?P<user>\w{1,16})\s*(?::|»|>>)\s+(?P<message>.*)$")
+                .unwrap(),
+            whispers: Whispers::default(),
+        }
+    }
+
+    /// Records whether each chat line was whispered in `whispers`
+    pub fn with_whispers(mut self, whispers: Whispers) -> Self {
+        self.whispers = whispers;
+        self
+    }
+
+    /// Parses a client or server log line into a chat message
+    ///
+    /// # Returns
+    /// `None` for anything that isn't chat sent by another player, including
+    /// join, death and advancement messages and whispers we sent
+    pub fn parse_chat(&self, line: &str) -> Option<MinecraftChat> {
+        let line = strip_formatting(line);
+
+        let body = match line.find(CHAT_MARKER) {
+            Some(index) => &line[index + CHAT_MARKER.len()..],
+            // Without the marker (e.g. the server log) only the vanilla format is chat
+            None => line
+                .find(LOG_PREFIX_END)
+                .map(|index| &line[index + LOG_PREFIX_END.len()..])
+                .map(|body| body.strip_prefix(NOT_SECURE).unwrap_or(body))
+                .filter(|body| body.starts_with('<'))?,
+        };
+        let body = body.trim();
+
+        if self.outgoing_whisper.is_match(body) {
+            return None;
+        }
+
+        let (captures, whisper) = if let Some(captures) = self.whisper.captures(body) {
+            (captures, true)
+        } else if let Some(captures) = self.vanilla.captures(body) {
+            (captures, false)
+        } else {
+            (self.ranked.captures(body)?, false)
+        };
+
+        let user = captures
+            .name("user")
+            .or_else(|| captures.name("essentials"))?
+            .as_str()
+            .to_string();
+        let message = captures.name("message")?.as_str().trim().to_string();
+
+        if message.is_empty() {
+            return None;
+        }
+
+        Some(MinecraftChat {
+            user,
+            message,
+            whisper,
+        })
+    }
+}
+
+impl Default for MinecraftParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParseLog for MinecraftParser {
+    fn parse_command(&self, line: &str) -> Option<ChatMessage> {
+        let chat = self.parse_chat(line)?;
+
+        self.whispers
+            .record(&chat.user, &chat.message, chat.whisper);
+
+        Some(super::to_chat_message(chat.user, chat.message))
+    }
+}
+
+/// Removes `§` color and style codes, e.g. `§aSteve§r` becomes `Steve`
+fn strip_formatting(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch == '§' {
+            chars.next();
+        } else {
+            stripped.push(ch);
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat(user: &str, message: &str, whisper: bool) -> Option<MinecraftChat> {
+        Some(MinecraftChat {
+            user: user.to_string(),
+            message: message.to_string(),
+            whisper,
+        })
+    }
+
+    #[test]
+    fn test_vanilla_chat() {
+        let parser = MinecraftParser::new();
+
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [System] [CHAT] <Steve> .ping"),
+            chat("Steve", ".ping", false)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Client thread/INFO]: [CHAT] <Alex> hello there"),
+            chat("Alex", "hello there", false)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Server thread/INFO]: <Steve> .calc 1+1"),
+            chat("Steve", ".calc 1+1", false)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] <[Admin] Notch> hi"),
+            chat("Notch", "hi", false)
+        );
+    }
+
+    #[test]
+    fn test_server_log_chat() {
+        let parser = MinecraftParser::new();
+
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Server thread/INFO]: [Not Secure] <Steve> .ping"),
+            chat("Steve", ".ping", false)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11 INFO]: <Alex> .calc 2*3"),
+            chat("Alex", ".calc 2*3", false)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Server thread/INFO]: [Rcon] pong"),
+            None
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Server thread/INFO]: Steve joined the game"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ranked_and_formatted_chat() {
+        let parser = MinecraftParser::new();
+
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] [VIP] Steve: .ping"),
+            chat("Steve", ".ping", false)
+        );
+        assert_eq!(
+            parser.parse_chat(
+                "[18:02:11] [Render thread/INFO]: [CHAT] §7[§6Owner§7] §cjeb_§7: §fwhat: time"
+            ),
+            chat("jeb_", "what: time", false)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] Member Alex » gg"),
+            chat("Alex", "gg", false)
+        );
+    }
+
+    #[test]
+    fn test_whispers() {
+        let parser = MinecraftParser::new();
+
+        assert_eq!(
+            parser
+                .parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] Steve whispers to you: .ping"),
+            chat("Steve", ".ping", true)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] [Alex -> me] secret"),
+            chat("Alex", "secret", true)
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] You whisper to Steve: hi"),
+            None
+        );
+        assert_eq!(
+            parser.parse_chat("[18:02:11] [Render thread/INFO]: [CHAT] [me -> Alex] hi"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_whispers_are_recorded() {
+        let whispers = Whispers::default();
+        let parser = MinecraftParser::new().with_whispers(whispers.clone());
+
+        let whispered = parser
+            .parse_command("[18:02:11] [Render thread/INFO]: [CHAT] Steve whispers to you: .ping")
+            .unwrap();
+
+        assert!(whispers.is_whisper(&whispered.user_name, &whispered.raw_message));
+
+        // The same message sent to everyone afterwards is answered in public
+        let public = parser
+            .parse_command("[18:02:12] [Render thread/INFO]: [CHAT] <Steve> .ping")
+            .unwrap();
+
+        assert!(!whispers.is_whisper(&public.user_name, &public.raw_message));
+        assert!(!whispers.is_whisper("Alex", ".ping"));
+    }
+
+    #[test]
+    fn test_system_messages_are_ignored() {
+        let parser = MinecraftParser::new();
+
+        for line in [
+            "[18:02:11] [Render thread/INFO]: [CHAT] Steve joined the game",
+            "[18:02:11] [Render thread/INFO]: [CHAT] Alex was slain by Zombie",
+            "[18:02:11] [Render thread/INFO]: [CHAT] Steve has made the advancement [Stone Age]",
+            "[18:02:11] [Render thread/INFO]: [CHAT] [Server] Restarting in 5 minutes",
+            "[18:02:11] [Server thread/INFO]: Steve lost connection: Disconnected",
+            "[18:02:11] [Render thread/INFO]: Loaded 7 advancements",
+        ] {
+            assert_eq!(parser.parse_chat(line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_empty_messages_do_not_panic() {
+        let parser = MinecraftParser::new();
+
+        assert!(parser.parse_command("[CHAT] <Steve> ").is_none());
+        assert!(parser.parse_command("[CHAT] [VIP] Steve: ").is_none());
+        assert!(parser.parse_command("[CHAT] ").is_none());
+        assert!(parser.parse_command("").is_none());
+
+        let message = parser.parse_command("[CHAT] <Steve> .ping   pong").unwrap();
+
+        assert_eq!(message.command, ".ping");
+        assert_eq!(message.message, "pong");
+    }
+}
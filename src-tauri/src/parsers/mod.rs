@@ -0,0 +1,33 @@
+mod custom;
+mod events;
+mod minecraft;
+mod source;
+
+pub use custom::{CustomParser, PatternMatch};
+pub use events::{EventParser, GameEvent};
+pub use minecraft::{MinecraftParser, Whispers};
+pub use source::{gmod_parser, source_parser};
+
+use source_cmd_parser::model::ChatMessage;
+
+/// Splits a chat line into the command (its first word) and the rest of the message
+///
+/// # Arguments
+/// user_name - Who sent the message
+/// raw_message - Everything they typed
+pub fn to_chat_message(user_name: String, raw_message: String) -> ChatMessage {
+    let command = raw_message
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let message = raw_message
+        .trim_start()
+        .strip_prefix(command.as_str())
+        .unwrap_or(&raw_message)
+        .trim()
+        .to_string();
+
+    ChatMessage::new(user_name, message, command, raw_message)
+}
@@ -0,0 +1,233 @@
+use super::CustomParser;
+
+/// Chat as printed by Source and GoldSrc games, e.g. `*DEAD*(TEAM) Player :  hello`.
+/// The engine always separates the name from the message with ` :  `, optionally after
+/// a `\u200e` mark, and only prefixes the teams below. Lines may start with a
+/// `con_timestamp` style timestamp.
+const SOURCE_CHAT: &str = r"^(?:L \d{2}/\d{2}/\d{4} - \d{2}:\d{2}:\d{2}: )?(?:(?P<dead>\*DEAD\*)|\*SPEC\*)?\s*(?:\((?P<team>TEAM|Counter-Terrorist|Terrorist|Spectator|Survivor|Infected)\)\s*)?(?P<user>[^\s(*].{0,31}?)\x{200e}? :  (?P<message>.+)$";
+
+/// Garry's Mod has no space before the colon, e.g. `*DEAD* (TEAM) Player: hello`.
+/// Names starting with `[` are skipped so Lua errors aren't read as chat.
+const GMOD_CHAT: &str = r"^(?P<dead>\*DEAD\* )?(?:\((?P<team>TEAM)\) )?(?P<user>[^\s\[(*](?:[^:]{0,30}[^\s:])?): (?P<message>.+)$";
+
+/// Console output that reads like chat, by what comes before the colon. Includes the
+/// lines of `status`, which is often run with the console log open.
+const CONSOLE_OUTPUT: [&str; 18] = [
+    "Map",
+    "Unknown command",
+    "Lua Error",
+    "Error",
+    "Warning",
+    "Host_Error",
+    "Steam Auth",
+    "Missing map material",
+    "hostname",
+    "version",
+    "udp/ip",
+    "steamid",
+    "account",
+    "map",
+    "tags",
+    "players",
+    "edicts",
+    "sourcetv",
+];
+
+/// Team Fortress 2, Left 4 Dead 2, Half-Life 2: Deathmatch and Counter-Strike 1.6
+pub fn source_parser() -> CustomParser {
+    CustomParser::new(SOURCE_CHAT)
+        .unwrap()
+        .ignoring_users(&CONSOLE_OUTPUT)
+}
+
+pub fn gmod_parser() -> CustomParser {
+    CustomParser::new(GMOD_CHAT)
+        .unwrap()
+        .ignoring_users(&CONSOLE_OUTPUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use source_cmd_parser::log_parser::ParseLog;
+
+    use super::*;
+    use crate::parsers::custom::CapturedLine;
+
+    fn captured(user: &str, message: &str, team: Option<&str>, dead: bool) -> Option<CapturedLine> {
+        Some(CapturedLine {
+            user: user.to_string(),
+            message: message.to_string(),
+            channel: None,
+            team: team.map(str::to_string),
+            dead,
+            steam_id: None,
+        })
+    }
+
+    /// Console output of each game that must never be read as chat
+    fn assert_not_chat(parser: &CustomParser, lines: &[&str]) {
+        for line in lines {
+            assert_eq!(parser.capture(line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_team_fortress_2() {
+        let parser = source_parser();
+
+        assert_eq!(
+            parser.capture("Heavy Weapons Guy :  .ping"),
+            captured("Heavy Weapons Guy", ".ping", None, false)
+        );
+        assert_eq!(
+            parser.capture("*DEAD*(TEAM) scout main :  .calc 2+2"),
+            captured("scout main", ".calc 2+2", Some("TEAM"), true)
+        );
+        assert_eq!(
+            parser.capture("*SPEC* spectator :  gg"),
+            captured("spectator", "gg", None, false)
+        );
+        assert_eq!(
+            parser.capture("(TEAM) Pyro\u{200e} :  .roll 2d6"),
+            captured("Pyro", ".roll 2d6", Some("TEAM"), false)
+        );
+
+        assert_not_chat(
+            &parser,
+            &[
+                "hostname: Valve Matchmaking Server (Virginia iad-1/srcds1001 #43)",
+                "version : 8622567/24 8622567 secure",
+                "udp/ip  : 169.254.1.1:27015",
+                "steamid : [A:1:1234567:12345] (90123456789012345)",
+                "account : not logged in  (No account specified)",
+                "map     : cp_badlands at: 0 x, 0 y, 0 z",
+                "tags    : cp,increased_maxplayers,valve",
+                "players : 23 humans, 0 bots (24 max)",
+                "edicts  : 1263 used of 2048 max",
+                "# userid name                uniqueid            connected ping loss state",
+                "Connected to 169.254.1.1:27015",
+                "Heavy Weapons Guy killed scout main with minigun.",
+                "Unknown command \"foo\"",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_left_4_dead_2() {
+        let parser = source_parser();
+
+        assert_eq!(
+            parser.capture("(Survivor) Ellis :  .ping"),
+            captured("Ellis", ".ping", Some("Survivor"), false)
+        );
+        assert_eq!(
+            parser.capture("*DEAD*(Infected) Smoker :  hi"),
+            captured("Smoker", "hi", Some("Infected"), true)
+        );
+
+        assert_not_chat(
+            &parser,
+            &[
+                "version : 2.2.3.6 8965 secure",
+                "map     : c2m1_highway at: -835 x, 2741 y, -860 z",
+                "players : 4 humans, 0 bots (4 max) (not hibernating)",
+                "Server Number: 1",
+                "Redownloading all lightmaps",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_half_life_2_deathmatch() {
+        let parser = source_parser();
+
+        assert_eq!(
+            parser.capture("L 03/14/2024 - 20:15:02: Gordon :  .ping"),
+            captured("Gordon", ".ping", None, false)
+        );
+        assert_eq!(
+            parser.capture("*DEAD* (TEAM) Alyx :  hello"),
+            captured("Alyx", "hello", Some("TEAM"), true)
+        );
+
+        assert_not_chat(
+            &parser,
+            &[
+                "Gordon :  ",
+                "(Rebels) Alyx :  not a team the engine prints",
+                "L 03/14/2024 - 20:15:02: hostname: Half-Life 2 Deathmatch",
+                "sourcetv:  port 27020, delay 30.0s",
+                "Compact freed 1261568 bytes",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_counter_strike_1_6() {
+        let parser = source_parser();
+
+        assert_eq!(
+            parser.capture("*DEAD*(Counter-Terrorist) Player :  .ping"),
+            captured("Player", ".ping", Some("Counter-Terrorist"), true)
+        );
+        assert_eq!(
+            parser.capture("(Terrorist) Player :  rush b"),
+            captured("Player", "rush b", Some("Terrorist"), false)
+        );
+
+        let message = parser.parse_command("Player :  .calc 1 + 1").unwrap();
+
+        assert_eq!(message.user_name, "Player");
+        assert_eq!(message.command, ".calc");
+        assert_eq!(message.message, "1 + 1");
+
+        assert_not_chat(
+            &parser,
+            &[
+                "players : 1 active (32 max)",
+                "map     :  de_dust2 at: 0 x, 0 y, 0 z",
+                "Player : single spaced",
+                "\"sv_cheats\" is \"0\"",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_garrys_mod() {
+        let parser = gmod_parser();
+
+        assert_eq!(
+            parser.capture("Garry: .ping"),
+            captured("Garry", ".ping", None, false)
+        );
+        assert_eq!(
+            parser.capture("*DEAD* (TEAM) Garry: .calc 5*5"),
+            captured("Garry", ".calc 5*5", Some("TEAM"), true)
+        );
+        assert_eq!(
+            parser.capture("(TEAM) Dr. Kleiner: who has the crowbar"),
+            captured("Dr. Kleiner", "who has the crowbar", Some("TEAM"), false)
+        );
+
+        assert_not_chat(
+            &parser,
+            &[
+                "[ERROR] lua/autorun/test.lua:3: attempt to index a nil value",
+                "Unknown command: foo",
+                "Lua Error: lua/autorun/client/hud.lua:12: unexpected symbol near ')'",
+                "Warning: Unable to find ConVar gmod_mcore_test",
+                "Steam Auth: Client 1 is not connected",
+                "Missing map material: TOOLS/TOOLSBLACK",
+                "ConVarRef mat_dxlevel doesn't point to an existing ConVar",
+                "hostname: Garry's Mod",
+                "version : 2023.06.28/24 9019 secure (unknown)",
+                "map     : gm_construct at: -1466 x, -1422 y, -79 z",
+                "players : 1 humans, 0 bots (16 max)",
+                "  1 \"Garry\" STEAM_0:1:1234 00:12 40 0 active",
+                "Couldn't include file 'autorun/x.lua' (File not found) (@lua/autorun/y.lua (line 1))",
+                "a killed b with c.",
+                "Map: gm_construct",
+            ],
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+/// Options are voted for by number, so there can only be 9
+const MAX_OPTIONS: usize = 9;
+
+/// A chat poll, open until its duration runs out
+#[derive(Debug, Clone)]
+pub struct Poll {
+    /// Tells this poll apart from one started after it, so its timer can't close that one
+    pub id: Uuid,
+    pub question: String,
+    options: Vec<String>,
+    /// The option each player voted for, players can change their vote
+    votes: HashMap<String, usize>,
+}
+
+impl Poll {
+    /// Starts a poll from `.poll` arguments like `Best map? dust2, mirage, inferno`
+    ///
+    /// # Arguments
+    /// arguments - The question ending with `?`, then the options separated by commas or
+    /// slashes. Without options it is a yes or no question.
+    ///
+    /// # Returns
+    /// `None` without a question, with a single option or with too many
+    pub fn parse(arguments: &str) -> Option<Self> {
+        let (question, options) = match arguments.split_once('?') {
+            Some((question, options)) => (format!("{}?", question.trim()), options),
+            None => (arguments.trim().to_string(), ""),
+        };
+
+        let mut options: Vec<String> = options
+            .split([',', '/'])
+            .map(|option| option.trim().to_string())
+            .filter(|option| !option.is_empty())
+            .collect();
+
+        if options.is_empty() {
+            options = vec!["yes".to_string(), "no".to_string()];
+        }
+
+        if question.trim_end_matches('?').is_empty()
+            || options.len() < 2
+            || options.len() > MAX_OPTIONS
+        {
+            return None;
+        }
+
+        Some(Self {
+            id: Uuid::new_v4(),
+            question,
+            options,
+            votes: HashMap::new(),
+        })
+    }
+
+    /// Tells chat how to vote
+    pub fn announcement(&self, duration: u64) -> String {
+        let options: Vec<String> = self
+            .options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| format!("{}) {}", index + 1, option))
+            .collect();
+
+        format!(
+            "Poll: {} Vote with {} ({}s)",
+            self.question,
+            options.join(" "),
+            duration
+        )
+    }
+
+    /// Counts a chat message as a vote if it is an option's number or name
+    ///
+    /// # Returns
+    /// Whether the message was a vote
+    pub fn vote(&mut self, player: &str, message: &str) -> bool {
+        let message = message.trim();
+
+        let option = match message.parse::<usize>() {
+            Ok(number) if (1..=self.options.len()).contains(&number) => Some(number - 1),
+            _ => self
+                .options
+                .iter()
+                .position(|option| option.eq_ignore_ascii_case(message)),
+        };
+
+        match option {
+            Some(option) => {
+                self.votes.insert(player.to_string(), option);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the running poll if it is still the one with `id`
+    ///
+    /// # Arguments
+    /// running - The running poll, if any
+    /// id - The poll whose time is up
+    ///
+    /// # Returns
+    /// The poll to post the results of, `None` if it was stopped or replaced since
+    pub fn close(running: &mut Option<Poll>, id: Uuid) -> Option<Poll> {
+        if running.as_ref().is_some_and(|poll| poll.id == id) {
+            running.take()
+        } else {
+            None
+        }
+    }
+
+    /// The votes for each option and the winner
+    pub fn results(&self) -> String {
+        let mut counts = vec![0; self.options.len()];
+
+        for option in self.votes.values() {
+            counts[*option] += 1;
+        }
+
+        let tally: Vec<String> = self
+            .options
+            .iter()
+            .zip(&counts)
+            .map(|(option, count)| format!("{} {}", option, count))
+            .collect();
+
+        let most = counts.iter().copied().max().unwrap_or_default();
+        let winners: Vec<&str> = self
+            .options
+            .iter()
+            .zip(&counts)
+            .filter(|(_, count)| **count == most)
+            .map(|(option, _)| option.as_str())
+            .collect();
+
+        let outcome = match winners.as_slice() {
+            _ if most == 0 => "Nobody voted".to_string(),
+            [winner] => format!("{} wins", winner),
+            tied => format!("Tie between {}", tied.join(", ")),
+        };
+
+        format!(
+            "Poll results: {} {}. {}",
+            self.question,
+            tally.join(", "),
+            outcome
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let poll = Poll::parse("Best map? dust2, mirage / inferno").unwrap();
+
+        assert_eq!(poll.question, "Best map?");
+        assert_eq!(poll.options, vec!["dust2", "mirage", "inferno"]);
+        assert_eq!(
+            poll.announcement(60),
+            "Poll: Best map? Vote with 1) dust2 2) mirage 3) inferno (60s)"
+        );
+
+        let yes_no = Poll::parse("Another round?").unwrap();
+
+        assert_eq!(yes_no.options, vec!["yes", "no"]);
+        assert!(Poll::parse("").is_none());
+        assert!(Poll::parse("? a, b").is_none());
+        assert!(Poll::parse("Which? only one").is_none());
+        assert!(Poll::parse("Which? 1,2,3,4,5,6,7,8,9,10").is_none());
+    }
+
+    #[test]
+    fn test_votes_and_results() {
+        let mut poll = Poll::parse("Best map? dust2, mirage, inferno").unwrap();
+
+        assert_eq!(
+            poll.results(),
+            "Poll results: Best map? dust2 0, mirage 0, inferno 0. Nobody voted"
+        );
+
+        assert!(poll.vote("Alyx", "1"));
+        assert!(poll.vote("Barney", "MIRAGE"));
+        assert!(poll.vote("Gordon", " mirage "));
+        assert!(!poll.vote("Gordon", "4"));
+        assert!(!poll.vote("Gordon", "mirage is better"));
+
+        assert_eq!(
+            poll.results(),
+            "Poll results: Best map? dust2 1, mirage 2, inferno 0. mirage wins"
+        );
+
+        // Changing a vote replaces the old one
+        assert!(poll.vote("Barney", "dust2"));
+
+        assert_eq!(
+            poll.results(),
+            "Poll results: Best map? dust2 2, mirage 1, inferno 0. dust2 wins"
+        );
+
+        assert!(poll.vote("Eli", "inferno"));
+        assert!(poll.vote("Gordon", "3"));
+
+        assert_eq!(
+            poll.results(),
+            "Poll results: Best map? dust2 2, mirage 0, inferno 2. Tie between dust2, inferno"
+        );
+    }
+
+    #[test]
+    fn test_close_only_ends_the_same_poll() {
+        let first_id = Poll::parse("Another round?").unwrap().id;
+        let second = Poll::parse("Best map? dust2, mirage").unwrap();
+        let second_id = second.id;
+
+        // The first poll was stopped and a second one started before its time was up
+        let mut running = Some(second);
+
+        assert!(Poll::close(&mut running, first_id).is_none());
+        assert_eq!(running.as_ref().map(|poll| poll.id), Some(second_id));
+
+        assert_eq!(
+            Poll::close(&mut running, second_id).map(|poll| poll.question),
+            Some("Best map?".to_string())
+        );
+        assert!(running.is_none());
+        assert!(Poll::close(&mut running, second_id).is_none());
+    }
+}
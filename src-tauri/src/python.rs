@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use log::error;
 use pyo3::{
-    types::{PyDict, PyModule, PyString},
-    PyErr, Python,
+    exceptions::PyRuntimeError,
+    types::{PyCFunction, PyDict, PyModule, PyString, PyTuple},
+    PyErr, PyResult, Python,
 };
 
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,8 @@ use source_cmd_parser::model::{ChatMessage, ChatResponse};
 use crate::{
     error::{SourceCmdGuiError, SourceCmdGuiResult},
     model::{entity::Script, state::Config},
+    parsers::GameEvent,
+    rcon::{say_command, RconClient},
 };
 
 pub trait ToPyDict {
@@ -33,6 +36,24 @@ impl ToPyDict for ChatMessage {
     }
 }
 
+impl ToPyDict for GameEvent {
+    fn to_py_dict(&self, py: Python<'_>) -> Result<pyo3::prelude::PyObject, PyErr> {
+        let dict = PyDict::new(py);
+
+        if let Ok(Value::Object(fields)) = serde_json::to_value(self) {
+            for (key, value) in fields {
+                match value {
+                    Value::String(value) => dict.set_item(key, value)?,
+                    Value::Bool(value) => dict.set_item(key, value)?,
+                    _ => dict.set_item(key, py.None())?,
+                }
+            }
+        }
+
+        Ok(dict.into())
+    }
+}
+
 impl ToPyDict for Config {
     fn to_py_dict(&self, py: Python<'_>) -> Result<pyo3::prelude::PyObject, PyErr> {
         let dict = PyDict::new(py);
@@ -40,7 +61,6 @@ impl ToPyDict for Config {
         dict.set_item("file_path", self.file_path.clone())?;
         dict.set_item("command_timeout", self.command_timeout)?;
         dict.set_item("owner", self.owner.clone())?;
-        dict.set_item("openai_api_key", self.openai_api_key.clone())?;
         dict.set_item("disabled_commands", self.disabled_commands.clone())?;
         dict.set_item("response_direction", self.response_direction.clone())?;
 
@@ -53,15 +73,102 @@ pub async fn process_python_command(
     message: ChatMessage,
     config: &Config,
     python_context: DynamicPythonCtx,
+    rcon: Option<Arc<RconClient>>,
+) -> SourceCmdGuiResult<(Option<ChatResponse>, Option<DynamicPythonCtx>)> {
+    run_script(script, "message", &message, config, python_context, rcon).await
+}
+
+/// Runs a script subscribed to a game event, the event is passed in as `event`
+pub async fn process_python_event(
+    script: &Script,
+    event: &GameEvent,
+    config: &Config,
+    python_context: DynamicPythonCtx,
+    rcon: Option<Arc<RconClient>>,
+) -> SourceCmdGuiResult<(Option<ChatResponse>, Option<DynamicPythonCtx>)> {
+    run_script(script, "event", event, config, python_context, rcon).await
+}
+
+/// Wraps the RCON client for scripts, `locals['rcon']("status")` returns the command's output
+/// and `locals['say']("gg")` says the text in chat, quoted so it can't run other commands
+///
+/// Blocks the script until the server answers, with the GIL released.
+///
+/// # Arguments
+/// name - The name scripts call it by
+/// doc - What it does, for `help()`
+/// to_command - Builds the server command from the script's argument
+fn rcon_function(
+    py: Python<'_>,
+    rcon: Arc<RconClient>,
+    name: &'static str,
+    doc: &'static str,
+    to_command: fn(&str) -> String,
+) -> PyResult<&PyCFunction> {
+    let runtime = tokio::runtime::Handle::current();
+
+    PyCFunction::new_closure(
+        py,
+        Some(name),
+        Some(doc),
+        move |args: &PyTuple, _kwargs: Option<&PyDict>| -> PyResult<String> {
+            let argument: String = args.get_item(0)?.extract()?;
+            let command = to_command(&argument);
+
+            args.py()
+                .allow_threads(|| {
+                    tokio::task::block_in_place(|| runtime.block_on(rcon.exec(&command)))
+                })
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+        },
+    )
+}
+
+async fn run_script(
+    script: &Script,
+    input_name: &str,
+    input: &impl ToPyDict,
+    config: &Config,
+    python_context: DynamicPythonCtx,
+    rcon: Option<Arc<RconClient>>,
 ) -> SourceCmdGuiResult<(Option<ChatResponse>, Option<DynamicPythonCtx>)> {
     let code = script.get_code().await?;
 
     let result: Result<(Option<String>, Option<String>), PyErr> = Python::with_gil(|py| {
         let locals = PyDict::new(py);
 
-        locals.set_item("message", message.to_py_dict(py)?)?;
+        locals.set_item(input_name, input.to_py_dict(py)?)?;
         locals.set_item("config", config.to_py_dict(py)?)?;
 
+        match rcon {
+            Some(rcon) => {
+                locals.set_item(
+                    "rcon",
+                    rcon_function(
+                        py,
+                        rcon.clone(),
+                        "rcon",
+                        "Runs a command on the server and returns its output",
+                        str::to_string,
+                    )?,
+                )?;
+                locals.set_item(
+                    "say",
+                    rcon_function(
+                        py,
+                        rcon,
+                        "say",
+                        "Says the text in chat on the server",
+                        say_command,
+                    )?,
+                )?;
+            }
+            None => {
+                locals.set_item("rcon", py.None())?;
+                locals.set_item("say", py.None())?;
+            }
+        }
+
         let serialized: String = python_context.try_into().unwrap_or("{}".to_owned());
 
         let py_string = PyString::new(py, &serialized);
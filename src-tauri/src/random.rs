@@ -0,0 +1,362 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use regex::Regex;
+
+/// Most dice one roll can throw, so the result fits in chat
+const MAX_DICE: u32 = 100;
+const MAX_SIDES: u32 = 1000;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum DiceError {
+    #[error("\"{0}\" is not dice notation, try 2d6+3 or 4d6kh3")]
+    Invalid(String),
+
+    #[error("Roll at most {} dice at once", MAX_DICE)]
+    TooManyDice,
+
+    #[error("Dice have 1 to {} sides", MAX_SIDES)]
+    InvalidSides,
+
+    #[error("Can't keep {0} of {1} dice")]
+    InvalidKeep(u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Term {
+    Dice {
+        count: u32,
+        sides: u32,
+        keep: Option<Keep>,
+    },
+    Modifier(u32),
+}
+
+/// The outcome of a roll
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceRoll {
+    pub total: i64,
+    /// Every die and modifier, e.g. `[4, 2] + 3`, with dropped dice in parentheses
+    pub detail: String,
+}
+
+/// Rolls dice, flips coins and picks options, seeded from the OS unless testing
+pub struct Randomizer {
+    rng: StdRng,
+    /// `2d6`, `d20`, `4d6kh3`, `2d20kl1` or a modifier like `3`
+    dice_term: Regex,
+}
+
+impl Default for Randomizer {
+    fn default() -> Self {
+        Self::new(StdRng::from_entropy())
+    }
+}
+
+impl Randomizer {
+    fn new(rng: StdRng) -> Self {
+        Self {
+            rng,
+            dice_term: Regex::new(r"^(?:(\d*)d(\d+)(?:k([hl]?)(\d+))?|(\d+))$").unwrap(),
+        }
+    }
+
+    /// A randomizer that always gives the same results, for tests
+    #[cfg(test)]
+    pub fn seeded(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+
+    /// Rolls dice notation like `2d6+3`, `d20`, `4d6kh3` (keep the highest 3) or `2d20kl1`
+    ///
+    /// # Arguments
+    /// notation - The dice to roll, a number alone rolls one die with that many sides and
+    /// nothing rolls a d6
+    ///
+    /// # Returns
+    /// The total and every die rolled, or what is wrong with the notation
+    pub fn roll(&mut self, notation: &str) -> Result<DiceRoll, DiceError> {
+        let terms = self.parse_dice(notation)?;
+        let mut total: i64 = 0;
+        let mut detail = String::new();
+
+        for (index, (negative, term)) in terms.into_iter().enumerate() {
+            let (value, text) = match term {
+                Term::Dice { count, sides, keep } => self.roll_dice(count, sides, keep),
+                Term::Modifier(value) => (i64::from(value), value.to_string()),
+            };
+
+            match (index, negative) {
+                (0, true) => detail.push('-'),
+                (0, false) => {}
+                (_, true) => detail.push_str(" - "),
+                (_, false) => detail.push_str(" + "),
+            }
+
+            detail.push_str(&text);
+            total += if negative { -value } else { value };
+        }
+
+        detail.push_str(&format!(" = {}", total));
+
+        Ok(DiceRoll { total, detail })
+    }
+
+    /// # Returns
+    /// Each term and whether it is subtracted
+    fn parse_dice(&self, notation: &str) -> Result<Vec<(bool, Term)>, DiceError> {
+        let notation: String = notation
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        let invalid = || DiceError::Invalid(notation.clone());
+
+        let notation = match notation.as_str() {
+            "" => "d6".to_string(),
+            sides if sides.chars().all(|c| c.is_ascii_digit()) => format!("d{}", sides),
+            _ => notation.clone(),
+        };
+
+        let mut terms = Vec::new();
+        let mut dice = 0;
+
+        for (negative, text) in split_terms(&notation) {
+            let captures = self.dice_term.captures(text).ok_or_else(invalid)?;
+            let number = |index: usize| {
+                captures
+                    .get(index)
+                    .map(|number| number.as_str().parse::<u32>().map_err(|_| invalid()))
+            };
+
+            let term = match number(5) {
+                Some(modifier) => Term::Modifier(modifier?),
+                None => {
+                    let count = match captures.get(1).map(|count| count.as_str()) {
+                        Some("") | None => 1,
+                        Some(_) => number(1).unwrap()?,
+                    };
+                    let sides = number(2).unwrap()?;
+
+                    if count == 0 {
+                        return Err(invalid());
+                    }
+
+                    if !(1..=MAX_SIDES).contains(&sides) {
+                        return Err(DiceError::InvalidSides);
+                    }
+
+                    dice += count;
+
+                    if dice > MAX_DICE {
+                        return Err(DiceError::TooManyDice);
+                    }
+
+                    let keep = match number(4).transpose()? {
+                        Some(kept) if kept == 0 || kept > count => {
+                            return Err(DiceError::InvalidKeep(kept, count));
+                        }
+                        Some(kept) if &captures[3] == "l" => Some(Keep::Lowest(kept)),
+                        Some(kept) => Some(Keep::Highest(kept)),
+                        None => None,
+                    };
+
+                    Term::Dice { count, sides, keep }
+                }
+            };
+
+            terms.push((negative, term));
+        }
+
+        if terms.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(terms)
+    }
+
+    /// # Returns
+    /// The sum of the kept dice and every die, e.g. `[6, 5, (1)]`
+    fn roll_dice(&mut self, count: u32, sides: u32, keep: Option<Keep>) -> (i64, String) {
+        let rolls: Vec<u32> = (0..count).map(|_| self.rng.gen_range(1..=sides)).collect();
+
+        let mut order: Vec<usize> = (0..rolls.len()).collect();
+        order.sort_by_key(|&index| rolls[index]);
+
+        let dropped = match keep {
+            Some(Keep::Highest(kept)) => &order[..(count - kept) as usize],
+            Some(Keep::Lowest(kept)) => &order[kept as usize..],
+            None => &[],
+        };
+
+        let mut total = 0;
+        let dice: Vec<String> = rolls
+            .iter()
+            .enumerate()
+            .map(|(index, roll)| {
+                if dropped.contains(&index) {
+                    format!("({})", roll)
+                } else {
+                    total += i64::from(*roll);
+                    roll.to_string()
+                }
+            })
+            .collect();
+
+        (total, format!("[{}]", dice.join(", ")))
+    }
+
+    /// Picks one of the options, separated by commas or, without any, by spaces
+    ///
+    /// # Returns
+    /// `None` if there are no options
+    pub fn pick(&mut self, options: &str) -> Option<String> {
+        let options: Vec<&str> = if options.contains(',') {
+            options.split(',').collect()
+        } else {
+            options.split_whitespace().collect()
+        };
+        let options: Vec<&str> = options
+            .into_iter()
+            .map(str::trim)
+            .filter(|option| !option.is_empty())
+            .collect();
+
+        options
+            .choose(&mut self.rng)
+            .map(|option| option.to_string())
+    }
+
+    pub fn flip_coin(&mut self) -> &'static str {
+        if self.rng.gen_bool(0.5) {
+            "heads"
+        } else {
+            "tails"
+        }
+    }
+}
+
+/// Splits `2d6+3-1` into `2d6`, `3` and `1`, marking the subtracted ones
+fn split_terms(notation: &str) -> Vec<(bool, &str)> {
+    let mut terms = Vec::new();
+    let mut negative = false;
+    let mut start = 0;
+
+    for (index, c) in notation.char_indices() {
+        if c == '+' || c == '-' {
+            terms.push((negative, &notation[start..index]));
+            negative = c == '-';
+            start = index + 1;
+        }
+    }
+
+    terms.push((negative, &notation[start..]));
+
+    // A leading sign leaves an empty first term, e.g. `-1+d6`
+    if terms[0].1.is_empty() && terms.len() > 1 {
+        terms.remove(0);
+    }
+
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_dice_notation() {
+        let mut randomizer = Randomizer::seeded(7);
+
+        for _ in 0..100 {
+            let roll = randomizer.roll("2d6 + 3").unwrap();
+
+            assert!((5..=15).contains(&roll.total));
+            assert!(roll.detail.ends_with(&format!("+ 3 = {}", roll.total)));
+        }
+
+        assert_eq!(
+            randomizer.roll("1d1+3-2d1").unwrap(),
+            DiceRoll {
+                total: 2,
+                detail: "[1] + 3 - [1, 1] = 2".to_string()
+            }
+        );
+        assert!((1..=20).contains(&randomizer.roll("D20").unwrap().total));
+        assert!((1..=100).contains(&randomizer.roll("100").unwrap().total));
+        assert!((1..=6).contains(&randomizer.roll("").unwrap().total));
+    }
+
+    #[test]
+    fn test_keep_highest_and_lowest() {
+        let mut randomizer = Randomizer::seeded(7);
+
+        for _ in 0..100 {
+            let roll = randomizer.roll("4d6kh3").unwrap();
+            let dice: Vec<&str> = roll.detail[1..roll.detail.find(']').unwrap()]
+                .split(", ")
+                .collect();
+            let dropped: Vec<u32> = dice
+                .iter()
+                .filter_map(|die| die.strip_prefix('(')?.strip_suffix(')')?.parse().ok())
+                .collect();
+            let kept: Vec<u32> = dice.iter().filter_map(|die| die.parse().ok()).collect();
+
+            assert_eq!(dropped.len(), 1);
+            assert!(kept.iter().all(|die| *die >= dropped[0]));
+            assert_eq!(roll.total, kept.iter().sum::<u32>() as i64);
+        }
+
+        assert_eq!(
+            randomizer.roll("3d1kl1").unwrap().detail,
+            "[1, (1), (1)] = 1"
+        );
+    }
+
+    #[test]
+    fn test_invalid_dice() {
+        let mut randomizer = Randomizer::seeded(7);
+
+        assert_eq!(
+            randomizer.roll("2d"),
+            Err(DiceError::Invalid("2d".to_string()))
+        );
+        assert_eq!(
+            randomizer.roll("d6++1"),
+            Err(DiceError::Invalid("d6++1".to_string()))
+        );
+        assert_eq!(randomizer.roll("60d6+50d6"), Err(DiceError::TooManyDice));
+        assert_eq!(randomizer.roll("d0"), Err(DiceError::InvalidSides));
+        assert_eq!(randomizer.roll("2d6kh3"), Err(DiceError::InvalidKeep(3, 2)));
+    }
+
+    #[test]
+    fn test_seeded_results_repeat() {
+        let mut first = Randomizer::seeded(42);
+        let mut second = Randomizer::seeded(42);
+
+        for _ in 0..20 {
+            assert_eq!(first.roll("3d20").unwrap(), second.roll("3d20").unwrap());
+            assert_eq!(first.flip_coin(), second.flip_coin());
+            assert_eq!(first.pick("a b c"), second.pick("a b c"));
+        }
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut randomizer = Randomizer::seeded(7);
+
+        for _ in 0..20 {
+            let picked = randomizer.pick("dust 2, mirage ,inferno").unwrap();
+
+            assert!(["dust 2", "mirage", "inferno"].contains(&picked.as_str()));
+            assert!(["a", "b"].contains(&randomizer.pick("a  b").unwrap().as_str()));
+        }
+
+        assert_eq!(randomizer.pick(" , "), None);
+    }
+}
@@ -0,0 +1,451 @@
+use std::{io, sync::Arc, time::Duration};
+
+use log::{info, warn};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Mutex,
+    time::timeout,
+};
+
+use crate::{
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    model::state::OutputConfig,
+    secrets::SharedSecretStore,
+};
+
+const SERVERDATA_AUTH: i32 = 3;
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+const SERVERDATA_EXECCOMMAND: i32 = 2;
+const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// The largest packet a server sends, Minecraft allows a 4096 byte body plus the header
+const MAX_PACKET_SIZE: i32 = 4096 + 10;
+
+/// How long the server has to answer before the connection is considered lost
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A packet of the Source RCON protocol
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    pub id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl Packet {
+    pub fn new(id: i32, kind: i32, body: &str) -> Self {
+        Self {
+            id,
+            kind,
+            body: body.to_string(),
+        }
+    }
+
+    /// Little endian size, id and type, followed by the null terminated body and an empty string
+    pub fn encode(&self) -> Vec<u8> {
+        let size = (self.body.len() + 10) as i32;
+        let mut bytes = Vec::with_capacity(size as usize + 4);
+
+        bytes.extend_from_slice(&size.to_le_bytes());
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&self.kind.to_le_bytes());
+        bytes.extend_from_slice(self.body.as_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+
+        bytes
+    }
+
+    pub async fn read(stream: &mut TcpStream) -> SourceCmdGuiResult<Self> {
+        let size = stream.read_i32_le().await?;
+
+        if !(10..=MAX_PACKET_SIZE).contains(&size) {
+            return Err(SourceCmdGuiError::RconError(format!(
+                "Invalid packet size {}",
+                size
+            )));
+        }
+
+        let id = stream.read_i32_le().await?;
+        let kind = stream.read_i32_le().await?;
+
+        let mut body = vec![0; size as usize - 8];
+        stream.read_exact(&mut body).await?;
+        body.truncate(body.len() - 2);
+
+        Ok(Self {
+            id,
+            kind,
+            body: String::from_utf8_lossy(&body).to_string(),
+        })
+    }
+}
+
+/// An authenticated connection
+struct Connection {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl Connection {
+    async fn open(address: &str, password: &str) -> SourceCmdGuiResult<Self> {
+        let stream = timeout(RESPONSE_TIMEOUT, TcpStream::connect(address))
+            .await
+            .map_err(|_| timed_out())??;
+
+        let mut connection = Self { stream, next_id: 1 };
+        let id = connection.send(SERVERDATA_AUTH, password).await?;
+
+        // Source servers send an empty response value before the auth response
+        loop {
+            let packet = connection.read().await?;
+
+            if packet.kind != SERVERDATA_AUTH_RESPONSE {
+                continue;
+            }
+
+            if packet.id != id {
+                return Err(SourceCmdGuiError::RconError(
+                    "Authentication failed, check the RCON password".to_string(),
+                ));
+            }
+
+            info!("Connected to RCON at {}", address);
+
+            return Ok(connection);
+        }
+    }
+
+    async fn exec(&mut self, command: &str) -> SourceCmdGuiResult<String> {
+        let id = self.send(SERVERDATA_EXECCOMMAND, command).await?;
+
+        // Responses over 4KB are split into several packets. Packets are answered in
+        // order, so the reply to an empty response value marks the end of the output.
+        let end_id = self.send(SERVERDATA_RESPONSE_VALUE, "").await?;
+        let mut output = String::new();
+
+        loop {
+            let packet = self.read().await?;
+
+            if packet.id == id {
+                output.push_str(&packet.body);
+            } else if packet.id == end_id {
+                return Ok(output);
+            }
+        }
+    }
+
+    async fn send(&mut self, kind: i32, body: &str) -> SourceCmdGuiResult<i32> {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+
+        self.stream
+            .write_all(&Packet::new(id, kind, body).encode())
+            .await?;
+
+        Ok(id)
+    }
+
+    async fn read(&mut self) -> SourceCmdGuiResult<Packet> {
+        timeout(RESPONSE_TIMEOUT, Packet::read(&mut self.stream))
+            .await
+            .map_err(|_| timed_out())?
+    }
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "The server did not respond")
+}
+
+/// Builds a `say` command for a message players may have written, quotes, semicolons and line
+/// breaks would end it early and run the rest as another command
+pub fn say_command(message: &str) -> String {
+    let message: String = message
+        .chars()
+        .map(|ch| match ch {
+            '"' => '\'',
+            ';' => ',',
+            '\n' | '\r' => ' ',
+            ch => ch,
+        })
+        .collect();
+
+    format!("say \"{}\"", message)
+}
+
+/// A client for a Source (or Minecraft) server's remote console
+///
+/// Connects on the first command and reconnects once when the connection was lost.
+pub struct RconClient {
+    address: String,
+    password: String,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl RconClient {
+    /// # Arguments
+    /// address - The server's `host:port`
+    /// password - The server's `rcon_password`
+    pub fn new(address: &str, password: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            password: password.to_string(),
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Builds the client for the server in the config
+    ///
+    /// # Returns
+    /// `None` if no RCON password is stored
+    pub fn from_config(
+        output: &OutputConfig,
+        secrets: &SharedSecretStore,
+    ) -> SourceCmdGuiResult<Option<Arc<Self>>> {
+        Ok(secrets
+            .get(&output.rcon_password_secret)?
+            .filter(|password| !password.is_empty())
+            .map(|password| Arc::new(Self::new(&output.rcon_address, &password))))
+    }
+
+    /// Runs a console command on the server
+    ///
+    /// # Returns
+    /// The command's output
+    pub async fn exec(&self, command: &str) -> SourceCmdGuiResult<String> {
+        let mut connection = self.connection.lock().await;
+        let mut reconnected = false;
+
+        loop {
+            let open = match connection.as_mut() {
+                Some(open) => open,
+                None => connection.insert(Connection::open(&self.address, &self.password).await?),
+            };
+
+            match open.exec(command).await {
+                Ok(output) => return Ok(output),
+                Err(e) => {
+                    *connection = None;
+
+                    if reconnected || !matches!(e, SourceCmdGuiError::IoError(_)) {
+                        return Err(e);
+                    }
+
+                    warn!("Lost the RCON connection, reconnecting: {}", e);
+                    reconnected = true;
+                }
+            }
+        }
+    }
+
+    /// Says `message` in chat on the server, it can't run any other command
+    pub async fn say(&self, message: &str) -> SourceCmdGuiResult<String> {
+        self.exec(&say_command(message)).await
+    }
+}
+
+/// A local stand-in for a game server's remote console
+#[cfg(test)]
+pub(crate) mod test_server {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex as StdMutex,
+    };
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    pub const PASSWORD: &str = "secret";
+
+    #[derive(Clone, Copy, PartialEq)]
+    pub enum Flavor {
+        /// Replies like `srcds`
+        Source,
+        /// Replies like a vanilla Minecraft server
+        Minecraft,
+    }
+
+    pub struct TestServer {
+        pub address: String,
+        connections: Arc<AtomicUsize>,
+        commands: Arc<StdMutex<Vec<String>>>,
+    }
+
+    impl TestServer {
+        /// # Arguments
+        /// flavor - Which server to imitate
+        /// one_shot - Close every connection after its first command
+        pub async fn start(flavor: Flavor, one_shot: bool) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let connections = Arc::new(AtomicUsize::new(0));
+            let commands = Arc::new(StdMutex::new(Vec::new()));
+
+            let server = Self {
+                address,
+                connections: connections.clone(),
+                commands: commands.clone(),
+            };
+
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    connections.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(serve(stream, flavor, one_shot, commands.clone()));
+                }
+            });
+
+            server
+        }
+
+        pub fn connections(&self) -> usize {
+            self.connections.load(Ordering::SeqCst)
+        }
+
+        /// Every command run so far
+        pub fn commands(&self) -> Vec<String> {
+            self.commands.lock().unwrap().clone()
+        }
+    }
+
+    async fn serve(
+        mut stream: TcpStream,
+        flavor: Flavor,
+        one_shot: bool,
+        commands: Arc<StdMutex<Vec<String>>>,
+    ) {
+        while let Ok(packet) = Packet::read(&mut stream).await {
+            let replies = match packet.kind {
+                SERVERDATA_AUTH if packet.body == PASSWORD => match flavor {
+                    Flavor::Source => vec![
+                        Packet::new(packet.id, SERVERDATA_RESPONSE_VALUE, ""),
+                        Packet::new(packet.id, SERVERDATA_AUTH_RESPONSE, ""),
+                    ],
+                    Flavor::Minecraft => vec![Packet::new(packet.id, SERVERDATA_AUTH_RESPONSE, "")],
+                },
+                SERVERDATA_AUTH => vec![Packet::new(-1, SERVERDATA_AUTH_RESPONSE, "")],
+                SERVERDATA_EXECCOMMAND => {
+                    commands.lock().unwrap().push(packet.body.clone());
+
+                    let output = match packet.body.split_once(' ') {
+                        Some(("repeat", count)) => "x".repeat(count.parse().unwrap()),
+                        _ => format!("ran {}", packet.body),
+                    };
+
+                    output
+                        .as_bytes()
+                        .chunks(4096)
+                        .map(|chunk| {
+                            Packet::new(
+                                packet.id,
+                                SERVERDATA_RESPONSE_VALUE,
+                                std::str::from_utf8(chunk).unwrap(),
+                            )
+                        })
+                        .collect()
+                }
+                _ => match flavor {
+                    // Mirrored, followed by the odd packet srcds always sends after it
+                    Flavor::Source => vec![
+                        Packet::new(packet.id, SERVERDATA_RESPONSE_VALUE, ""),
+                        Packet::new(packet.id, SERVERDATA_RESPONSE_VALUE, "\0\u{1}\0\0"),
+                    ],
+                    Flavor::Minecraft => vec![Packet::new(
+                        packet.id,
+                        SERVERDATA_RESPONSE_VALUE,
+                        &format!("Unknown request {:x}", packet.kind),
+                    )],
+                },
+            };
+
+            for reply in replies {
+                stream.write_all(&reply.encode()).await.unwrap();
+            }
+
+            if one_shot && packet.kind == SERVERDATA_RESPONSE_VALUE {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        test_server::{Flavor, TestServer, PASSWORD},
+        *,
+    };
+
+    #[tokio::test]
+    async fn test_exec() {
+        let server = TestServer::start(Flavor::Source, false).await;
+        let client = RconClient::new(&server.address, PASSWORD);
+
+        assert_eq!(client.exec("status").await.unwrap(), "ran status");
+        assert_eq!(client.exec("say hi").await.unwrap(), "ran say hi");
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password() {
+        let server = TestServer::start(Flavor::Source, false).await;
+        let client = RconClient::new(&server.address, "wrong");
+
+        assert!(matches!(
+            client.exec("status").await,
+            Err(SourceCmdGuiError::RconError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_multi_packet_response() {
+        let server = TestServer::start(Flavor::Source, false).await;
+        let client = RconClient::new(&server.address, PASSWORD);
+
+        assert_eq!(client.exec("repeat 10000").await.unwrap().len(), 10000);
+        assert_eq!(client.exec("echo").await.unwrap(), "ran echo");
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_after_disconnect() {
+        let server = TestServer::start(Flavor::Source, true).await;
+        let client = RconClient::new(&server.address, PASSWORD);
+
+        assert_eq!(client.exec("first").await.unwrap(), "ran first");
+        assert_eq!(client.exec("second").await.unwrap(), "ran second");
+        assert_eq!(server.connections(), 2);
+    }
+
+    #[test]
+    fn test_say_command_escapes_separators() {
+        assert_eq!(say_command("hi there"), "say \"hi there\"");
+        assert_eq!(
+            say_command("say \"x\"; quit\nkill"),
+            "say \"say 'x', quit kill\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_say() {
+        let server = TestServer::start(Flavor::Source, false).await;
+        let client = RconClient::new(&server.address, PASSWORD);
+
+        client.say("gg").await.unwrap();
+        client.say("hi\"; rcon_password x\r\nquit").await.unwrap();
+
+        assert_eq!(
+            server.commands(),
+            vec!["say \"gg\"", "say \"hi', rcon_password x  quit\""]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_minecraft_server() {
+        let server = TestServer::start(Flavor::Minecraft, false).await;
+        let client = RconClient::new(&server.address, PASSWORD);
+
+        assert_eq!(client.exec("list").await.unwrap(), "ran list");
+        assert_eq!(client.exec("repeat 5000").await.unwrap().len(), 5000);
+        assert_eq!(server.commands(), vec!["list", "repeat 5000"]);
+    }
+}
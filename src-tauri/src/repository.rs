@@ -1,13 +1,22 @@
 use std::path::Path;
 
+use chrono::Utc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::{
     fs::{File, OpenOptions},
     io::{AsyncReadExt, AsyncWriteExt},
 };
 
 use crate::{
+    config,
     error::{SourceCmdGuiError, SourceCmdGuiResult},
-    model::entity::Script,
+    model::entity::{Profile, Script},
+    moderation::{ModerationHit, ModerationLog},
+    parsers::GameEvent,
+    secrets::SharedSecretStore,
+    stats::{PlayerStats, StatsStore},
 };
 
 pub trait ScriptRepository {
@@ -138,3 +147,690 @@ def main(args):
             .cloned())
     }
 }
+
+pub trait ProfileRepository {
+    async fn init(&mut self) -> SourceCmdGuiResult;
+    fn get_profiles(&self) -> Vec<Profile>;
+    fn get_profile(&self, name: &str) -> SourceCmdGuiResult<Profile>;
+    fn get_active_profile(&self) -> Option<Profile>;
+    async fn add_profile(&mut self, profile: Profile) -> SourceCmdGuiResult<Profile>;
+    async fn update_profile(&mut self, profile: Profile) -> SourceCmdGuiResult;
+    async fn delete_profile(&mut self, name: &str) -> SourceCmdGuiResult;
+    async fn set_active_profile(&mut self, name: Option<&str>) -> SourceCmdGuiResult;
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ProfileStore {
+    active_profile: Option<String>,
+    profiles: Vec<Profile>,
+}
+
+pub struct JsonProfileRepository {
+    store: ProfileStore,
+    file_path: String,
+    secrets: SharedSecretStore,
+}
+
+impl JsonProfileRepository {
+    pub async fn new(file_path: String, secrets: SharedSecretStore) -> Self {
+        JsonProfileRepository {
+            store: ProfileStore::default(),
+            file_path,
+            secrets,
+        }
+    }
+
+    async fn read_from_file(&mut self) -> SourceCmdGuiResult {
+        let path = Path::new(&self.file_path);
+
+        if path.exists() {
+            let mut file = File::open(path).await?;
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).await?;
+
+            let mut value: Value = serde_json::from_str(&contents)?;
+            let mut profiles = Vec::new();
+            let mut upgraded = false;
+            let mut skipped = false;
+
+            let raw_profiles = value
+                .get_mut("profiles")
+                .and_then(Value::as_array_mut)
+                .map(std::mem::take)
+                .unwrap_or_default();
+
+            for raw_profile in raw_profiles {
+                let name = raw_profile["name"].as_str().unwrap_or_default().to_string();
+
+                match self.load_profile(raw_profile) {
+                    Ok((profile, profile_upgraded)) => {
+                        upgraded |= profile_upgraded;
+                        profiles.push(profile);
+                    }
+                    Err(e) => {
+                        warn!("Skipping profile {:?}, it could not be loaded: {}", name, e);
+                        skipped = true;
+                    }
+                }
+            }
+
+            self.store = ProfileStore {
+                active_profile: value["active_profile"].as_str().map(str::to_string),
+                profiles,
+            };
+
+            // Keep the skipped profiles around so they can be fixed by hand
+            if skipped {
+                tokio::fs::write(format!("{}.bak", self.file_path), &contents).await?;
+            }
+
+            if upgraded {
+                self.write_to_file().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Upgrades a profile's embedded config the same way the main config is upgraded
+    ///
+    /// # Returns
+    /// The profile and whether its config had to be upgraded
+    fn load_profile(&self, mut profile: Value) -> SourceCmdGuiResult<(Profile, bool)> {
+        let name = profile["name"].as_str().unwrap_or_default().to_string();
+        let mut upgraded = false;
+
+        if let Some(config) = profile.get_mut("config") {
+            let mut migrated = config::migrate(config.take())?;
+
+            // Scope extracted secrets so profiles don't overwrite each other's keys
+            for (secret_name, secret) in migrated.secrets {
+                let scoped_name = format!("profile.{}.{}", name, secret_name);
+
+                self.secrets.set(&scoped_name, &secret)?;
+                migrated.config.rename_secret(&secret_name, &scoped_name);
+            }
+
+            upgraded = migrated.upgraded;
+            *config = serde_json::to_value(migrated.config)?;
+        }
+
+        Ok((serde_json::from_value(profile)?, upgraded))
+    }
+
+    async fn write_to_file(&self) -> Result<(), std::io::Error> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.file_path)
+            .await?;
+
+        let contents = serde_json::to_string(&self.store)?;
+        file.write_all(contents.as_bytes()).await?;
+
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> SourceCmdGuiResult<usize> {
+        self.store
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| SourceCmdGuiError::ProfileNotFound(name.to_string()))
+    }
+}
+
+impl ProfileRepository for JsonProfileRepository {
+    async fn init(&mut self) -> SourceCmdGuiResult {
+        // Losing the profiles must not keep the app from starting, like a broken config.json
+        if let Err(e) = self.read_from_file().await {
+            warn!("Failed to load profiles, starting without any: {}", e);
+
+            // Keep the broken file, the next change would overwrite it
+            tokio::fs::copy(&self.file_path, format!("{}.bak", self.file_path)).await?;
+        }
+
+        Ok(())
+    }
+
+    fn get_profiles(&self) -> Vec<Profile> {
+        self.store.profiles.clone()
+    }
+
+    fn get_profile(&self, name: &str) -> SourceCmdGuiResult<Profile> {
+        Ok(self.store.profiles[self.position(name)?].clone())
+    }
+
+    fn get_active_profile(&self) -> Option<Profile> {
+        self.store
+            .active_profile
+            .as_deref()
+            .and_then(|name| self.get_profile(name).ok())
+    }
+
+    async fn add_profile(&mut self, mut profile: Profile) -> SourceCmdGuiResult<Profile> {
+        profile.name = profile.name.trim().to_string();
+
+        if profile.name.is_empty() {
+            return Err(SourceCmdGuiError::InvalidProfileName);
+        }
+
+        if self.position(&profile.name).is_ok() {
+            return Err(SourceCmdGuiError::ProfileAlreadyExists(profile.name));
+        }
+
+        self.store.profiles.push(profile.clone());
+        self.write_to_file().await?;
+
+        Ok(profile)
+    }
+
+    async fn update_profile(&mut self, profile: Profile) -> SourceCmdGuiResult {
+        let pos = self.position(&profile.name)?;
+
+        self.store.profiles[pos] = profile;
+        self.write_to_file().await?;
+
+        Ok(())
+    }
+
+    async fn delete_profile(&mut self, name: &str) -> SourceCmdGuiResult {
+        let pos = self.position(name)?;
+
+        self.store.profiles.remove(pos);
+
+        if self.store.active_profile.as_deref() == Some(name) {
+            self.store.active_profile = None;
+        }
+
+        self.write_to_file().await?;
+
+        Ok(())
+    }
+
+    async fn set_active_profile(&mut self, name: Option<&str>) -> SourceCmdGuiResult {
+        if let Some(name) = name {
+            self.position(name)?;
+        }
+
+        self.store.active_profile = name.map(str::to_string);
+        self.write_to_file().await?;
+
+        Ok(())
+    }
+}
+
+pub trait StatsRepository {
+    async fn init(&mut self) -> SourceCmdGuiResult;
+    async fn record_message(&mut self, user: &str, command: Option<&str>) -> SourceCmdGuiResult;
+    async fn record_event(&mut self, event: &GameEvent) -> SourceCmdGuiResult;
+    /// Writes the stats recorded since the last flush
+    async fn flush(&mut self) -> SourceCmdGuiResult;
+    fn get_player(&self, name: &str) -> Option<PlayerStats>;
+    fn get_players(&self) -> Vec<PlayerStats>;
+}
+
+/// Records every chat line, so changes are only kept in memory until `flush`
+pub struct JsonStatsRepository {
+    store: StatsStore,
+    file_path: String,
+    /// Whether the store changed since it was last written
+    dirty: bool,
+}
+
+impl JsonStatsRepository {
+    pub async fn new(file_path: String) -> Self {
+        JsonStatsRepository {
+            store: StatsStore::default(),
+            file_path,
+            dirty: false,
+        }
+    }
+
+    async fn read_from_file(&mut self) -> Result<(), std::io::Error> {
+        let path = Path::new(&self.file_path);
+
+        if path.exists() {
+            let mut file = File::open(path).await?;
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).await?;
+            self.store = serde_json::from_str(&contents)?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_to_file(&self) -> Result<(), std::io::Error> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.file_path)
+            .await?;
+
+        let contents = serde_json::to_string(&self.store)?;
+        file.write_all(contents.as_bytes()).await?;
+
+        Ok(())
+    }
+}
+
+impl StatsRepository for JsonStatsRepository {
+    async fn init(&mut self) -> SourceCmdGuiResult {
+        // Stats aren't worth keeping the app from starting, like broken profiles
+        if let Err(e) = self.read_from_file().await {
+            warn!("Failed to load player stats, starting without any: {}", e);
+
+            // Keep the broken file, the next flush would overwrite it
+            tokio::fs::copy(&self.file_path, format!("{}.bak", self.file_path)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn record_message(&mut self, user: &str, command: Option<&str>) -> SourceCmdGuiResult {
+        self.store.record_message(user, command, Utc::now());
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    async fn record_event(&mut self, event: &GameEvent) -> SourceCmdGuiResult {
+        if self.store.record_event(event, Utc::now()) {
+            self.dirty = true;
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> SourceCmdGuiResult {
+        if self.dirty {
+            self.write_to_file().await?;
+            self.dirty = false;
+        }
+
+        Ok(())
+    }
+
+    fn get_player(&self, name: &str) -> Option<PlayerStats> {
+        self.store.get(name).cloned()
+    }
+
+    fn get_players(&self) -> Vec<PlayerStats> {
+        self.store.players()
+    }
+}
+
+pub trait ModerationRepository {
+    async fn init(&mut self) -> SourceCmdGuiResult;
+    async fn record(&mut self, hit: ModerationHit) -> SourceCmdGuiResult;
+    async fn clear(&mut self) -> SourceCmdGuiResult;
+    /// Writes the hits recorded since the last flush
+    async fn flush(&mut self) -> SourceCmdGuiResult;
+    fn get_hits(&self) -> Vec<ModerationHit>;
+}
+
+/// Hits come in bursts during spam waves, so they are only kept in memory until `flush`
+pub struct JsonModerationRepository {
+    log: ModerationLog,
+    file_path: String,
+    /// Whether the log changed since it was last written
+    dirty: bool,
+}
+
+impl JsonModerationRepository {
+    pub async fn new(file_path: String) -> Self {
+        JsonModerationRepository {
+            log: ModerationLog::default(),
+            file_path,
+            dirty: false,
+        }
+    }
+
+    async fn read_from_file(&mut self) -> Result<(), std::io::Error> {
+        let path = Path::new(&self.file_path);
+
+        if path.exists() {
+            let mut file = File::open(path).await?;
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).await?;
+            self.log = serde_json::from_str(&contents)?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_to_file(&self) -> Result<(), std::io::Error> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.file_path)
+            .await?;
+
+        let contents = serde_json::to_string(&self.log)?;
+        file.write_all(contents.as_bytes()).await?;
+
+        Ok(())
+    }
+}
+
+impl ModerationRepository for JsonModerationRepository {
+    async fn init(&mut self) -> SourceCmdGuiResult {
+        // Like broken stats, a broken log must not keep the app from starting
+        if let Err(e) = self.read_from_file().await {
+            warn!(
+                "Failed to load the moderation log, starting with an empty one: {}",
+                e
+            );
+
+            // Keep the broken file, the next flush would overwrite it
+            tokio::fs::copy(&self.file_path, format!("{}.bak", self.file_path)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn record(&mut self, hit: ModerationHit) -> SourceCmdGuiResult {
+        self.log.record(hit);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    async fn clear(&mut self) -> SourceCmdGuiResult {
+        self.log.clear();
+        self.write_to_file().await?;
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> SourceCmdGuiResult {
+        if self.dirty {
+            self.write_to_file().await?;
+            self.dirty = false;
+        }
+
+        Ok(())
+    }
+
+    fn get_hits(&self) -> Vec<ModerationHit> {
+        self.log.hits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        model::{state::Config, ModerationAction},
+        secrets::{EncryptedFileSecretStore, SecretStore},
+    };
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("source-cmd-gui-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    async fn open_profiles(dir: &Path) -> JsonProfileRepository {
+        let mut repository = JsonProfileRepository::new(
+            dir.join("profiles.json").to_string_lossy().to_string(),
+            Arc::new(EncryptedFileSecretStore::new(dir)),
+        )
+        .await;
+        repository.init().await.unwrap();
+
+        repository
+    }
+
+    #[tokio::test]
+    async fn test_profiles() {
+        let dir = temp_dir();
+        let mut repository = open_profiles(&dir).await;
+
+        let config = Config {
+            owner: "Gordon".to_string(),
+            ..Config::default()
+        };
+        let created = repository
+            .add_profile(Profile::new(
+                " casual ".to_string(),
+                config,
+                vec!["a".to_string()],
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(created.name, "casual");
+        assert!(matches!(
+            repository
+                .add_profile(Profile::new(
+                    "casual".to_string(),
+                    Config::default(),
+                    vec![]
+                ))
+                .await,
+            Err(SourceCmdGuiError::ProfileAlreadyExists(_))
+        ));
+        assert!(matches!(
+            repository
+                .add_profile(Profile::new(" ".to_string(), Config::default(), vec![]))
+                .await,
+            Err(SourceCmdGuiError::InvalidProfileName)
+        ));
+
+        // Cloning is a copy under a new name
+        let mut clone = repository.get_profile("casual").unwrap();
+        clone.name = "competitive".to_string();
+        repository.add_profile(clone).await.unwrap();
+
+        repository
+            .set_active_profile(Some("competitive"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            repository.set_active_profile(Some("missing")).await,
+            Err(SourceCmdGuiError::ProfileNotFound(_))
+        ));
+
+        // Everything is read back from disk
+        let mut repository = open_profiles(&dir).await;
+        let active = repository.get_active_profile().unwrap();
+
+        assert_eq!(active.name, "competitive");
+        assert_eq!(active.config.owner, "Gordon");
+        assert_eq!(active.enabled_scripts, vec!["a"]);
+        assert_eq!(repository.get_profiles().len(), 2);
+
+        // Deleting the active profile leaves none active
+        repository.delete_profile("competitive").await.unwrap();
+
+        assert!(repository.get_active_profile().is_none());
+        assert!(matches!(
+            repository.delete_profile("competitive").await,
+            Err(SourceCmdGuiError::ProfileNotFound(_))
+        ));
+        assert_eq!(open_profiles(&dir).await.get_profiles().len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_profile_secrets_are_scoped() {
+        let dir = temp_dir();
+        let profiles = json!({
+            "active_profile": "casual",
+            "profiles": [
+                { "name": "casual", "config": { "version": 1, "openai_api_key": "sk-casual" }, "enabled_scripts": [] },
+                { "name": "ranked", "config": { "version": 1, "openai_api_key": "sk-ranked" }, "enabled_scripts": [] }
+            ]
+        });
+        std::fs::write(dir.join("profiles.json"), profiles.to_string()).unwrap();
+
+        let repository = open_profiles(&dir).await;
+        let secrets = EncryptedFileSecretStore::new(&dir);
+
+        for (name, key) in [("casual", "sk-casual"), ("ranked", "sk-ranked")] {
+            let secret = repository
+                .get_profile(name)
+                .unwrap()
+                .config
+                .openai_api_key_secret;
+
+            assert_eq!(secret, format!("profile.{}.openai_api_key", name));
+            assert_eq!(secrets.get(&secret).unwrap().as_deref(), Some(key));
+        }
+
+        // The upgraded profiles are written back without the keys
+        let contents = std::fs::read_to_string(dir.join("profiles.json")).unwrap();
+
+        assert!(!contents.contains("sk-"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_broken_profiles_are_skipped() {
+        let dir = temp_dir();
+        let profiles = json!({
+            "active_profile": "future",
+            "profiles": [
+                { "name": "casual", "config": Config::default(), "enabled_scripts": [] },
+                { "name": "future", "config": { "version": 99 }, "enabled_scripts": [] },
+                { "name": "broken" }
+            ]
+        });
+        std::fs::write(dir.join("profiles.json"), profiles.to_string()).unwrap();
+
+        let repository = open_profiles(&dir).await;
+
+        assert_eq!(repository.get_profiles().len(), 1);
+        assert!(repository.get_active_profile().is_none());
+        assert!(dir.join("profiles.json.bak").exists());
+
+        // A file that isn't json at all starts empty too
+        std::fs::write(dir.join("profiles.json"), "{").unwrap();
+
+        assert!(open_profiles(&dir).await.get_profiles().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("profiles.json.bak")).unwrap(),
+            "{"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    async fn open_stats(dir: &Path) -> JsonStatsRepository {
+        let mut repository =
+            JsonStatsRepository::new(dir.join("stats.json").to_string_lossy().to_string()).await;
+        repository.init().await.unwrap();
+
+        repository
+    }
+
+    #[tokio::test]
+    async fn test_stats_are_written_on_flush() {
+        let dir = temp_dir();
+        let mut repository = open_stats(&dir).await;
+
+        repository.record_message("Gordon", None).await.unwrap();
+        repository
+            .record_message("Gordon", Some(".ping"))
+            .await
+            .unwrap();
+
+        assert!(!dir.join("stats.json").exists());
+
+        repository.flush().await.unwrap();
+
+        let stats = open_stats(&dir).await.get_player("Gordon").unwrap();
+        assert_eq!(stats.messages, 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_broken_stats_start_empty() {
+        let dir = temp_dir();
+        std::fs::write(dir.join("stats.json"), "{").unwrap();
+
+        let mut repository = open_stats(&dir).await;
+        assert!(repository.get_players().is_empty());
+
+        repository.record_message("Gordon", None).await.unwrap();
+        repository.flush().await.unwrap();
+
+        assert_eq!(open_stats(&dir).await.get_players().len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("stats.json.bak")).unwrap(),
+            "{"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    async fn open_moderation_log(dir: &Path) -> JsonModerationRepository {
+        let mut repository = JsonModerationRepository::new(
+            dir.join("moderation.json").to_string_lossy().to_string(),
+        )
+        .await;
+        repository.init().await.unwrap();
+
+        repository
+    }
+
+    fn hit(player: &str) -> ModerationHit {
+        ModerationHit {
+            time: Utc::now(),
+            player: player.to_string(),
+            message: "noob".to_string(),
+            reason: "banned word \"noob\"".to_string(),
+            action: ModerationAction::Log,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_moderation_hits_are_written_on_flush() {
+        let dir = temp_dir();
+        let mut repository = open_moderation_log(&dir).await;
+
+        repository.record(hit("Gordon")).await.unwrap();
+        repository.record(hit("Alyx")).await.unwrap();
+
+        assert!(!dir.join("moderation.json").exists());
+
+        repository.flush().await.unwrap();
+
+        assert_eq!(open_moderation_log(&dir).await.get_hits().len(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_broken_moderation_log_starts_empty() {
+        let dir = temp_dir();
+        std::fs::write(dir.join("moderation.json"), "[{").unwrap();
+
+        let mut repository = open_moderation_log(&dir).await;
+        assert!(repository.get_hits().is_empty());
+
+        repository.record(hit("Gordon")).await.unwrap();
+        repository.flush().await.unwrap();
+
+        assert_eq!(open_moderation_log(&dir).await.get_hits().len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("moderation.json.bak")).unwrap(),
+            "[{"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use source_cmd_parser::{
+    log_parser::SourceCmdLogParser,
+    model::{ChatMessage, ChatResponse},
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    commands,
+    cooldown::CommandCooldown,
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    events, identity,
+    loop_guard::LoopBreaker,
+    model::state::{AppState, Config},
+    output::{self, OutgoingQueue, Priority},
+    parsers::Whispers,
+    rcon::RconClient,
+    supervisor::{ParserStatus, RunStatus},
+    watcher::{Backoff, LogFileEvent, LogTailer, LogWatcher},
+};
+
+/// How often the log file is checked for rotation and the stop flag for a stop request
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+enum RunOutcome {
+    /// The parser returned on its own
+    Exited(SourceCmdGuiResult),
+    /// The log file was replaced or truncated and has to be reopened
+    LogChanged(LogFileEvent),
+    Stopped,
+}
+
+/// Runs the parser until `stop_flag` is set
+///
+/// The parser is rebuilt whenever the log file is truncated, rotated or
+/// re-created, since it only follows the file it opened. If `auto_restart`
+/// is enabled, failed runs are retried with exponential backoff.
+///
+/// Game events are read from the start of the new file, so none are lost to a
+/// rotation. The chat parser only reads lines written after it opens the file,
+/// so chat written between the change being noticed (up to `POLL_INTERVAL`)
+/// and the new parser starting is missed.
+///
+/// # Arguments
+/// config - The config to build the parser from
+/// state - The app state handed to commands
+/// stop_flag - Set by the supervisor to request a stop
+/// status - The status of this run
+pub async fn run_parser(
+    config: Config,
+    state: Arc<Mutex<AppState>>,
+    stop_flag: Arc<AtomicBool>,
+    status: RunStatus,
+) -> SourceCmdGuiResult {
+    let file_path = PathBuf::from(&config.file_path);
+    let max_chat_length = config.parser.get_max_chat_length(&config.custom_parser);
+    let rcon = {
+        let mut state = state.lock().await;
+
+        // Scripts can run server commands through the same connection
+        state.cmd_state.rcon = RconClient::from_config(&config.output, &state.secrets)?;
+        state.cmd_state.rcon.clone()
+    };
+    let output = output::build(&config, rcon)?;
+    let (queue, player_ids) = {
+        let state = state.lock().await;
+
+        (state.outgoing.clone(), state.cmd_state.player_ids.clone())
+    };
+    let whispers = Whispers::default();
+    let mut backoff = Backoff::default();
+    let mut log_changed = false;
+    let cooldown = Arc::new(CommandCooldown::default());
+
+    loop {
+        // Only this loop stops the parser, so it can be restarted independently of the supervisor
+        let parser_stop_flag = Arc::new(AtomicBool::new(false));
+
+        let mut builder = SourceCmdLogParser::builder()
+            .file_path(Box::new(file_path.clone()))
+            .state(state.clone())
+            .set_parser(
+                config
+                    .parser
+                    .get_parser(&config.custom_parser, &player_ids, &whispers)?,
+            )
+            .chat_key(config.parser.get_chat_key(&config.custom_parser))
+            .stop_flag(parser_stop_flag.clone())
+            // The owner and timeout are read from the live config below, so they can change while running
+            .time_out(Duration::ZERO);
+
+        // Shared by every command of this run, so a restart gives halted commands another chance
+        let breaker = Arc::new(LoopBreaker::default());
+
+        for command in commands::get_commands() {
+            let queue = queue.clone();
+            let breaker = breaker.clone();
+            let cooldown = cooldown.clone();
+            let whispers = whispers.clone();
+            let global_command = command.global_command;
+            let id = command.id.clone();
+            let command_id = id.clone();
+
+            let handler = move |msg: ChatMessage, state: Arc<Mutex<AppState>>| {
+                let player = msg.user_name.clone();
+
+                let own_output = is_own_output(&queue, &msg);
+
+                // Checked now, the parser records whispers as it hands each line over
+                let whisper = whispers.is_whisper(&msg.user_name, &msg.raw_message);
+
+                // Call the function in the trait object
+                let response = command.command.call(msg, state.clone());
+                let queue = queue.clone();
+                let breaker = breaker.clone();
+                let cooldown = cooldown.clone();
+                let id = id.clone();
+
+                async move {
+                    if own_output {
+                        return Ok(None);
+                    }
+
+                    let (priority, allowed) = {
+                        let state = state.lock().await;
+                        let is_owner =
+                            identity::is_owner(&player, &state.config, &state.cmd_state.player_ids);
+                        let timeout = Duration::from_secs(state.config.command_timeout);
+
+                        // Global commands see every message, so only commands make players wait
+                        let allowed = is_owner
+                            || global_command
+                            || cooldown.allow(&player, timeout, Instant::now());
+
+                        (response_priority(is_owner, global_command), allowed)
+                    };
+
+                    if !allowed {
+                        return Ok(None);
+                    }
+
+                    let response = response.await?;
+
+                    if response.is_some() && !breaker.allow(&id, Instant::now()) {
+                        return Ok(None);
+                    }
+
+                    queue_response(
+                        response,
+                        &queue,
+                        &player,
+                        whisper,
+                        priority,
+                        max_chat_length,
+                    )
+                }
+            };
+
+            if global_command {
+                builder = builder.add_global_command(handler);
+            } else {
+                builder = builder.add_command(&command_id, handler);
+            }
+        }
+
+        let mut parser = builder.build()?;
+        let mut watcher = LogWatcher::new(file_path.clone());
+        let started = Instant::now();
+
+        status.running();
+
+        let run = parser.run();
+        tokio::pin!(run);
+
+        // A new or truncated log only holds lines written since the change, so events read
+        // all of it instead of missing those written while the parser was being rebuilt
+        let tailer = if log_changed {
+            LogTailer::from_start(file_path.clone())
+        } else {
+            LogTailer::new(file_path.clone())
+        };
+
+        // Dropped along with the run, so it follows restarts of the parser
+        let events = events::dispatch(tailer, config.clone(), state.clone(), queue.clone());
+        tokio::pin!(events);
+
+        let sending = queue.run(output.clone());
+        tokio::pin!(sending);
+
+        let outcome = tokio::select! {
+            result = &mut run => RunOutcome::Exited(result.map_err(SourceCmdGuiError::from)),
+            never = &mut events => match never {},
+            never = &mut sending => match never {},
+            event = watch(&mut watcher, &stop_flag) => {
+                parser_stop_flag.store(true, Ordering::Relaxed);
+
+                if let Err(e) = (&mut run).await {
+                    warn!("Parser returned an error while stopping: {}", e);
+                }
+
+                event.map_or(RunOutcome::Stopped, RunOutcome::LogChanged)
+            }
+        };
+
+        match outcome {
+            RunOutcome::Stopped => return Ok(()),
+            RunOutcome::LogChanged(event) => {
+                info!("Log file was {}, reopening it", event);
+                status.set(ParserStatus::Starting);
+                log_changed = true;
+            }
+            RunOutcome::Exited(result) => {
+                log_changed = false;
+
+                if stop_flag.load(Ordering::Relaxed) {
+                    return result;
+                }
+
+                let error = match result {
+                    Ok(()) => "The parser exited unexpectedly".to_string(),
+                    Err(e) => e.to_string(),
+                };
+
+                // Read live so auto restart can be turned off while the parser is failing
+                if !state.lock().await.config.auto_restart {
+                    return Err(SourceCmdGuiError::ParserExited(error));
+                }
+
+                let delay = backoff.next_delay(started.elapsed());
+
+                error!(
+                    "Parser failed, restarting in {} seconds: {}",
+                    delay.as_secs(),
+                    error
+                );
+                status.set(ParserStatus::Restarting(error));
+
+                if sleep_unless_stopped(delay, &stop_flag).await {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Queues a command's response for the output backend instead of letting the parser type it
+///
+/// # Arguments
+/// response - What the command responded with
+/// queue - The outgoing response queue
+/// player - Who ran the command
+/// whisper - Whether the player whispered the command, to whisper the response back
+/// priority - Where the response goes in the queue
+/// max_chat_length - The longest message the game accepts
+///
+/// # Returns
+/// Always `None` once queued, so the parser doesn't send it again
+fn queue_response(
+    response: Option<ChatResponse>,
+    queue: &OutgoingQueue,
+    player: &str,
+    whisper: bool,
+    priority: Priority,
+    max_chat_length: Option<usize>,
+) -> SourceCmdGuiResult<Option<ChatResponse>> {
+    if let Some(response) = response {
+        let message = limit_length(response.message, max_chat_length);
+
+        if whisper {
+            queue.push_whisper(message, player, priority);
+        } else {
+            queue.push(message, Some(player), priority);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether a chat line is a response the bot sent, responses typed into chat show up in the
+/// log like any other message
+///
+/// Compared as typed, `message` is only the text after the first word.
+fn is_own_output(queue: &OutgoingQueue, msg: &ChatMessage) -> bool {
+    queue.was_sent_recently(&msg.raw_message)
+}
+
+/// Owner commands go first, responses to every message go last
+fn response_priority(is_owner: bool, global_command: bool) -> Priority {
+    if is_owner {
+        Priority::High
+    } else if global_command {
+        Priority::Low
+    } else {
+        Priority::Normal
+    }
+}
+
+/// Cuts a message off at the longest message the game accepts
+pub fn limit_length(message: String, max_chat_length: Option<usize>) -> String {
+    match max_chat_length {
+        Some(max) if message.chars().count() > max => message.chars().take(max).collect(),
+        _ => message,
+    }
+}
+
+/// Waits until the log file needs reopening, or returns `None` once a stop is requested
+async fn watch(watcher: &mut LogWatcher, stop_flag: &AtomicBool) -> Option<LogFileEvent> {
+    loop {
+        if stop_flag.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        if let Some(event) = watcher.poll() {
+            info!("Log file was {}", event);
+
+            if event.requires_restart() {
+                return Some(event);
+            }
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// # Returns
+/// Whether a stop was requested while sleeping
+async fn sleep_unless_stopped(duration: Duration, stop_flag: &AtomicBool) -> bool {
+    let deadline = Instant::now() + duration;
+
+    while Instant::now() < deadline {
+        if stop_flag.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL.min(deadline - Instant::now())).await;
+    }
+
+    stop_flag.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use source_cmd_parser::log_parser::ParseLog;
+
+    use super::*;
+    use crate::{
+        output::{CaptureOutput, SharedOutput},
+        parsers,
+    };
+
+    #[tokio::test]
+    async fn test_sent_responses_are_own_output() {
+        let queue = Arc::new(OutgoingQueue::new(None));
+        let output = Arc::new(CaptureOutput::default());
+
+        queue.push("Welcome, Gordon!".to_string(), None, Priority::Low);
+        queue.push("pong".to_string(), Some("Gordon"), Priority::Normal);
+
+        let sending = tokio::spawn({
+            let queue = queue.clone();
+            let output: SharedOutput = output.clone();
+
+            async move { queue.run(output).await }
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while output.messages().len() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        sending.abort();
+
+        // The responses as the game logs them once the bot typed them
+        let parser = parsers::source_parser();
+        let line = |line: &str| parser.parse_command(line).unwrap();
+
+        assert!(is_own_output(&queue, &line("Bot :  Welcome, Gordon!")));
+        assert!(is_own_output(&queue, &line("Bot :  pong")));
+        assert!(!is_own_output(&queue, &line("Gordon :  pong please")));
+        assert!(!is_own_output(&queue, &line("Gordon :  Welcome")));
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{info, warn};
+
+use crate::error::{SourceCmdGuiError, SourceCmdGuiResult};
+
+const KEYRING_SERVICE: &str = "source-cmd-gui";
+const NONCE_LEN: usize = 12;
+
+pub type SharedSecretStore = Arc<dyn SecretStore + Send + Sync>;
+
+/// Storage for values that must never be written to `config.json`, such as API keys.
+/// Secrets are referenced by name from the config.
+pub trait SecretStore {
+    fn get(&self, name: &str) -> SourceCmdGuiResult<Option<String>>;
+    fn set(&self, name: &str, value: &str) -> SourceCmdGuiResult;
+    fn delete(&self, name: &str) -> SourceCmdGuiResult;
+}
+
+/// Opens the OS keyring, falling back to an encrypted file in `dir` when no
+/// keyring service is available (e.g. headless Linux without a secret service).
+pub fn open_secret_store(dir: &Path) -> SharedSecretStore {
+    let keyring = KeyringSecretStore;
+
+    match keyring.get("__probe__") {
+        Ok(_) => {
+            info!("Using the OS keyring for secrets");
+            Arc::new(keyring)
+        }
+        Err(e) => {
+            warn!(
+                "OS keyring unavailable, using encrypted file for secrets: {}",
+                e
+            );
+            Arc::new(EncryptedFileSecretStore::new(dir))
+        }
+    }
+}
+
+pub struct KeyringSecretStore;
+
+impl SecretStore for KeyringSecretStore {
+    fn get(&self, name: &str) -> SourceCmdGuiResult<Option<String>> {
+        match keyring::Entry::new(KEYRING_SERVICE, name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> SourceCmdGuiResult {
+        Ok(keyring::Entry::new(KEYRING_SERVICE, name)?.set_password(value)?)
+    }
+
+    fn delete(&self, name: &str) -> SourceCmdGuiResult {
+        match keyring::Entry::new(KEYRING_SERVICE, name)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Secrets encrypted with AES-256-GCM in `secrets.json`.
+///
+/// The key lives next to the secrets in `secrets.key` (readable only by the
+/// owner on unix), so this keeps keys out of config files, backups and
+/// screenshots rather than protecting them from someone with access to the account.
+pub struct EncryptedFileSecretStore {
+    secrets_path: PathBuf,
+    key_path: PathBuf,
+    // Serializes read-modify-write cycles on the secrets file
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileSecretStore {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            secrets_path: dir.join("secrets.json"),
+            key_path: dir.join("secrets.key"),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn cipher(&self) -> SourceCmdGuiResult<Aes256Gcm> {
+        let key = match fs::read(&self.key_path) {
+            Ok(key) if key.len() == 32 => key,
+            Ok(_) => {
+                return Err(SourceCmdGuiError::SecretStoreError(
+                    "secrets.key is corrupt".to_string(),
+                ))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng).to_vec();
+                write_private(&self.key_path, &key)?;
+
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+
+    fn read_all(&self) -> SourceCmdGuiResult<HashMap<String, String>> {
+        match fs::read_to_string(&self.secrets_path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_all(&self, secrets: &HashMap<String, String>) -> SourceCmdGuiResult {
+        write_private(
+            &self.secrets_path,
+            serde_json::to_string(secrets)?.as_bytes(),
+        )
+    }
+}
+
+impl SecretStore for EncryptedFileSecretStore {
+    fn get(&self, name: &str) -> SourceCmdGuiResult<Option<String>> {
+        let _guard = self.lock.lock().unwrap();
+
+        let Some(encoded) = self.read_all()?.remove(name) else {
+            return Ok(None);
+        };
+
+        let invalid = || SourceCmdGuiError::SecretStoreError(format!("{} is corrupt", name));
+
+        let data = STANDARD.decode(encoded).map_err(|_| invalid())?;
+
+        if data.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+
+        Ok(Some(String::from_utf8(plaintext).map_err(|_| invalid())?))
+    }
+
+    fn set(&self, name: &str, value: &str) -> SourceCmdGuiResult {
+        let _guard = self.lock.lock().unwrap();
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|e| SourceCmdGuiError::SecretStoreError(e.to_string()))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+
+        let mut secrets = self.read_all()?;
+        secrets.insert(name.to_string(), STANDARD.encode(data));
+
+        self.write_all(&secrets)
+    }
+
+    fn delete(&self, name: &str) -> SourceCmdGuiResult {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut secrets = self.read_all()?;
+
+        if secrets.remove(name).is_some() {
+            self.write_all(&secrets)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_private(path: &Path, contents: &[u8]) -> SourceCmdGuiResult {
+    fs::write(path, contents)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("source-cmd-gui-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_encrypted_file_round_trip() {
+        let dir = temp_dir("secrets");
+        let store = EncryptedFileSecretStore::new(&dir);
+
+        assert_eq!(store.get("openai_api_key").unwrap(), None);
+
+        store.set("openai_api_key", "sk-test").unwrap();
+        assert_eq!(
+            store.get("openai_api_key").unwrap().as_deref(),
+            Some("sk-test")
+        );
+
+        // The plain value must not end up on disk
+        let contents = fs::read_to_string(dir.join("secrets.json")).unwrap();
+        assert!(!contents.contains("sk-test"));
+
+        store.delete("openai_api_key").unwrap();
+        assert_eq!(store.get("openai_api_key").unwrap(), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
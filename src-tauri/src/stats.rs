@@ -0,0 +1,199 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::parsers::GameEvent;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub name: String,
+    pub messages: u64,
+    /// Uses of each command and script trigger
+    pub commands: HashMap<String, u64>,
+    pub kills: u64,
+    pub deaths: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl PlayerStats {
+    fn new(name: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            name: name.to_string(),
+            messages: 0,
+            commands: HashMap::new(),
+            kills: 0,
+            deaths: 0,
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+
+    /// A one line summary to send in chat
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} messages", self.messages)];
+        let command_uses: u64 = self.commands.values().sum();
+
+        if let Some((command, _)) = self
+            .commands
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        {
+            parts.push(format!("{} commands (mostly {})", command_uses, command));
+        }
+
+        if self.kills > 0 || self.deaths > 0 {
+            parts.push(format!("{} kills, {} deaths", self.kills, self.deaths));
+        }
+
+        parts.push(format!("first seen {}", self.first_seen.format("%Y-%m-%d")));
+
+        format!("{}: {}", self.name, parts.join(", "))
+    }
+}
+
+/// Stats of every player seen, keyed by lowercase name
+#[derive(Default, Serialize, Deserialize)]
+pub struct StatsStore {
+    players: HashMap<String, PlayerStats>,
+}
+
+impl StatsStore {
+    fn seen(&mut self, name: &str, now: DateTime<Utc>) -> &mut PlayerStats {
+        let stats = self
+            .players
+            .entry(name.to_lowercase())
+            .or_insert_with(|| PlayerStats::new(name, now));
+
+        // Keep the latest spelling of the name
+        stats.name = name.to_string();
+        stats.last_seen = now;
+
+        stats
+    }
+
+    /// # Arguments
+    /// user - Who sent the message
+    /// command - The command or script trigger the message used, if any
+    /// now - When the message was sent
+    pub fn record_message(&mut self, user: &str, command: Option<&str>, now: DateTime<Utc>) {
+        let stats = self.seen(user, now);
+
+        stats.messages += 1;
+
+        if let Some(command) = command {
+            *stats.commands.entry(command.to_string()).or_default() += 1;
+        }
+    }
+
+    /// # Returns
+    /// Whether the event changed any stats
+    pub fn record_event(&mut self, event: &GameEvent, now: DateTime<Utc>) -> bool {
+        match event {
+            GameEvent::Kill { killer, victim, .. } => {
+                if killer != victim {
+                    self.seen(killer, now).kills += 1;
+                }
+
+                self.seen(victim, now).deaths += 1;
+            }
+            GameEvent::PlayerConnected { player }
+            | GameEvent::PlayerDisconnected { player, .. } => {
+                self.seen(player, now);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Finds a player by name, falling back to a unique partial match
+    pub fn get(&self, name: &str) -> Option<&PlayerStats> {
+        let name = name.trim().to_lowercase();
+
+        if let Some(stats) = self.players.get(&name) {
+            return Some(stats);
+        }
+
+        let mut matches = self.players.iter().filter(|(key, _)| key.contains(&name));
+
+        match (matches.next(), matches.next()) {
+            (Some((_, stats)), None) => Some(stats),
+            _ => None,
+        }
+    }
+
+    /// Every player, most recently seen first
+    pub fn players(&self) -> Vec<PlayerStats> {
+        let mut players: Vec<PlayerStats> = self.players.values().cloned().collect();
+        players.sort_by_key(|player| Reverse(player.last_seen));
+
+        players
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_record_messages_and_events() {
+        let mut store = StatsStore::default();
+
+        store.record_message("Gordon", None, at(1));
+        store.record_message("gordon", Some(".calc"), at(2));
+        store.record_message("Gordon", Some(".calc"), at(3));
+        store.record_message("Gordon", Some(".ping"), at(3));
+
+        assert!(store.record_event(
+            &GameEvent::Kill {
+                killer: "Gordon".to_string(),
+                victim: "Alyx".to_string(),
+                weapon: None,
+                headshot: false,
+            },
+            at(4)
+        ));
+        assert!(!store.record_event(&GameEvent::RoundStart, at(4)));
+        store.record_message("Gordon", None, at(5));
+
+        let gordon = store.get("GORDON").unwrap();
+
+        assert_eq!(gordon.messages, 5);
+        assert_eq!(gordon.kills, 1);
+        assert_eq!(gordon.first_seen, at(1));
+        assert_eq!(gordon.last_seen, at(5));
+        assert_eq!(
+            gordon.summary(),
+            "Gordon: 5 messages, 3 commands (mostly .calc), 1 kills, 0 deaths, first seen 2024-03-01"
+        );
+
+        assert_eq!(store.get("aly").unwrap().deaths, 1);
+        assert_eq!(
+            store
+                .players()
+                .iter()
+                .map(|player| player.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Gordon", "Alyx"]
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_partial_match() {
+        let mut store = StatsStore::default();
+
+        store.record_message("Player1", None, at(1));
+        store.record_message("Player2", None, at(1));
+
+        assert!(store.get("player").is_none());
+        assert_eq!(store.get("player2").unwrap().name, "Player2");
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use log::{error, info, warn};
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::error::{SourceCmdGuiError, SourceCmdGuiResult};
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "state", content = "error")]
+pub enum ParserStatus {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    /// Waiting to restart after the parser failed
+    Restarting(String),
+    Crashed(String),
+}
+
+impl ParserStatus {
+    /// Whether a parser thread is alive and owned by the supervisor
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            ParserStatus::Starting
+                | ParserStatus::Running
+                | ParserStatus::Stopping
+                | ParserStatus::Restarting(_)
+        )
+    }
+}
+
+struct StatusInner {
+    status: ParserStatus,
+    last_error: Option<String>,
+    /// Incremented on every start so a thread that outlived a stop timeout
+    /// can't overwrite the status of the run that replaced it
+    run: u64,
+}
+
+/// The status of one parser run, shared with the parser thread
+#[derive(Clone)]
+pub struct RunStatus {
+    inner: Arc<Mutex<StatusInner>>,
+    sender: Option<mpsc::Sender<ParserStatus>>,
+    run: u64,
+}
+
+impl RunStatus {
+    pub fn set(&self, status: ParserStatus) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.run != self.run || inner.status == status {
+            return;
+        }
+
+        if let ParserStatus::Crashed(error) | ParserStatus::Restarting(error) = &status {
+            inner.last_error = Some(error.clone());
+        }
+
+        info!("Parser status: {:?}", status);
+
+        inner.status = status.clone();
+
+        if let Some(sender) = &self.sender {
+            let _ = sender.try_send(status);
+        }
+    }
+
+    pub fn running(&self) {
+        self.set(ParserStatus::Running);
+    }
+}
+
+/// Owns the parser thread and tracks its status
+pub struct ParserSupervisor {
+    inner: Arc<Mutex<StatusInner>>,
+    sender: Option<mpsc::Sender<ParserStatus>>,
+    stop_flag: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ParserSupervisor {
+    /// # Arguments
+    /// sender - Receives every status change, forwarded to the frontend
+    pub fn new(sender: Option<mpsc::Sender<ParserStatus>>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(StatusInner {
+                status: ParserStatus::Stopped,
+                last_error: None,
+                run: 0,
+            })),
+            sender,
+            stop_flag: Arc::default(),
+            thread: None,
+        }
+    }
+
+    pub fn status(&self) -> ParserStatus {
+        self.inner.lock().unwrap().status.clone()
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.inner.lock().unwrap().last_error.clone()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status().is_active()
+    }
+
+    fn current_run(&self) -> RunStatus {
+        RunStatus {
+            inner: self.inner.clone(),
+            sender: self.sender.clone(),
+            run: self.inner.lock().unwrap().run,
+        }
+    }
+
+    /// Runs the parser on its own thread and tokio runtime
+    ///
+    /// # Arguments
+    /// run - Builds and runs the parser. It receives the stop flag to hand to the
+    /// parser and should mark the run as running once the parser is built.
+    pub fn start<F, Fut>(&mut self, run: F) -> SourceCmdGuiResult
+    where
+        F: FnOnce(Arc<AtomicBool>, RunStatus) -> Fut + Send + 'static,
+        Fut: Future<Output = SourceCmdGuiResult>,
+    {
+        if self.is_active() {
+            return Err(SourceCmdGuiError::ProcessAlreadyRunning);
+        }
+
+        self.inner.lock().unwrap().run += 1;
+
+        let status = self.current_run();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+
+        status.set(ParserStatus::Starting);
+        self.stop_flag = stop_flag.clone();
+
+        self.thread = Some(std::thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let rt = tokio::runtime::Runtime::new()?;
+
+                rt.block_on(run(stop_flag.clone(), status.clone()))
+            }));
+
+            let stopped = stop_flag.load(Ordering::Relaxed);
+
+            let final_status = match result {
+                Ok(Ok(())) if stopped => ParserStatus::Stopped,
+                Ok(Ok(())) => ParserStatus::Crashed("The parser exited unexpectedly".to_string()),
+                Ok(Err(e)) if stopped => {
+                    warn!("Parser returned an error while stopping: {}", e);
+                    ParserStatus::Stopped
+                }
+                Ok(Err(e)) => ParserStatus::Crashed(e.to_string()),
+                Err(_) => ParserStatus::Crashed("The parser panicked".to_string()),
+            };
+
+            if let ParserStatus::Crashed(e) = &final_status {
+                error!("Parser crashed: {}", e);
+            }
+
+            status.set(final_status);
+        }));
+
+        Ok(())
+    }
+
+    /// Signals the parser to stop
+    ///
+    /// The returned future waits for the thread to exit, so it can be awaited
+    /// after releasing any lock held on the supervisor.
+    ///
+    /// # Arguments
+    /// timeout - How long to wait before giving up on the thread
+    pub fn stop(&mut self, timeout: Duration) -> impl Future<Output = SourceCmdGuiResult> {
+        let thread = self.thread.take();
+        let status = self.current_run();
+
+        if self.is_active() {
+            status.set(ParserStatus::Stopping);
+        }
+
+        self.stop_flag.store(true, Ordering::Relaxed);
+
+        async move {
+            let Some(thread) = thread else {
+                return Ok(());
+            };
+
+            let join = tokio::task::spawn_blocking(move || thread.join());
+
+            match tokio::time::timeout(timeout, join).await {
+                Ok(_) => Ok(()),
+                Err(_) => {
+                    let error = SourceCmdGuiError::StopTimedOut(timeout);
+
+                    // The thread is left behind, it will exit whenever the parser notices the flag
+                    status.set(ParserStatus::Crashed(error.to_string()));
+
+                    Err(error)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn wait_for(supervisor: &ParserSupervisor, expected: fn(&ParserStatus) -> bool) {
+        for _ in 0..100 {
+            if expected(&supervisor.status()) {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        panic!("Unexpected status {:?}", supervisor.status());
+    }
+
+    #[tokio::test]
+    async fn test_stop() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let mut supervisor = ParserSupervisor::new(Some(tx));
+
+        supervisor
+            .start(|stop_flag, status| async move {
+                status.running();
+
+                while !stop_flag.load(Ordering::Relaxed) {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+
+                Ok(())
+            })
+            .unwrap();
+
+        wait_for(&supervisor, |status| *status == ParserStatus::Running).await;
+        assert!(matches!(
+            supervisor.start(|_, _| async { Ok(()) }),
+            Err(SourceCmdGuiError::ProcessAlreadyRunning)
+        ));
+
+        supervisor.stop(Duration::from_secs(5)).await.unwrap();
+
+        assert_eq!(supervisor.status(), ParserStatus::Stopped);
+        assert_eq!(rx.recv().await, Some(ParserStatus::Starting));
+        assert_eq!(rx.recv().await, Some(ParserStatus::Running));
+        assert_eq!(rx.recv().await, Some(ParserStatus::Stopping));
+        assert_eq!(rx.recv().await, Some(ParserStatus::Stopped));
+    }
+
+    #[tokio::test]
+    async fn test_crash_is_reported() {
+        let mut supervisor = ParserSupervisor::new(None);
+
+        supervisor
+            .start(|_, _| async { Err(SourceCmdGuiError::ScriptNotFound("test".to_string())) })
+            .unwrap();
+
+        wait_for(&supervisor, |status| {
+            matches!(status, ParserStatus::Crashed(_))
+        })
+        .await;
+
+        assert!(!supervisor.is_active());
+        assert!(supervisor.last_error().unwrap().contains("test"));
+    }
+
+    #[tokio::test]
+    async fn test_stop_times_out() {
+        let mut supervisor = ParserSupervisor::new(None);
+
+        // Ignores the stop flag
+        supervisor
+            .start(|_, status| async move {
+                status.running();
+                std::thread::sleep(Duration::from_millis(500));
+
+                Ok(())
+            })
+            .unwrap();
+
+        wait_for(&supervisor, |status| *status == ParserStatus::Running).await;
+
+        let error = supervisor
+            .stop(Duration::from_millis(10))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, SourceCmdGuiError::StopTimedOut(_)));
+        assert_eq!(
+            error.to_string(),
+            "The parser did not stop within 0.010 seconds"
+        );
+        assert!(!supervisor.is_active());
+    }
+}
@@ -0,0 +1,276 @@
+use chatgpt::{prelude::ChatGPT, types::CompletionResponse};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use whatlang::{Lang, Script};
+
+use crate::{
+    error::{SourceCmdGuiError, SourceCmdGuiResult},
+    model::{state::TranslationConfig, TranslationBackend},
+};
+
+/// Guesses below this on short Latin script messages are usually wrong, e.g. "lol nice shot"
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// How long the translation endpoint has to answer, so a hung server can't stall a command
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Languages the detector recognises that translation services share a code for
+const LANGUAGES: [(Lang, &str); 40] = [
+    (Lang::Eng, "en"),
+    (Lang::Rus, "ru"),
+    (Lang::Cmn, "zh"),
+    (Lang::Spa, "es"),
+    (Lang::Por, "pt"),
+    (Lang::Ita, "it"),
+    (Lang::Fra, "fr"),
+    (Lang::Deu, "de"),
+    (Lang::Ukr, "uk"),
+    (Lang::Ara, "ar"),
+    (Lang::Hin, "hi"),
+    (Lang::Jpn, "ja"),
+    (Lang::Heb, "he"),
+    (Lang::Pol, "pl"),
+    (Lang::Kor, "ko"),
+    (Lang::Nob, "nb"),
+    (Lang::Dan, "da"),
+    (Lang::Swe, "sv"),
+    (Lang::Fin, "fi"),
+    (Lang::Tur, "tr"),
+    (Lang::Nld, "nl"),
+    (Lang::Hun, "hu"),
+    (Lang::Ces, "cs"),
+    (Lang::Ell, "el"),
+    (Lang::Bul, "bg"),
+    (Lang::Ron, "ro"),
+    (Lang::Slv, "sl"),
+    (Lang::Hrv, "hr"),
+    (Lang::Srp, "sr"),
+    (Lang::Lit, "lt"),
+    (Lang::Lav, "lv"),
+    (Lang::Est, "et"),
+    (Lang::Vie, "vi"),
+    (Lang::Tha, "th"),
+    (Lang::Ind, "id"),
+    (Lang::Pes, "fa"),
+    (Lang::Slk, "sk"),
+    (Lang::Cat, "ca"),
+    (Lang::Tgl, "tl"),
+    (Lang::Bel, "be"),
+];
+
+/// Translates chat messages
+#[async_trait::async_trait]
+pub trait Translator: Send + Sync {
+    /// # Arguments
+    /// text - What to translate
+    /// target - The language to translate to, an ISO 639-1 code like `en`
+    async fn translate(&self, text: &str, target: &str) -> SourceCmdGuiResult<String>;
+}
+
+/// Asks ChatGPT, the same client `explain` uses
+pub struct ChatGptTranslator {
+    client: ChatGPT,
+}
+
+#[async_trait::async_trait]
+impl Translator for ChatGptTranslator {
+    async fn translate(&self, text: &str, target: &str) -> SourceCmdGuiResult<String> {
+        let response: CompletionResponse = self
+            .client
+            .send_message(format!(
+                "Translate this game chat message to the language with the ISO 639-1 code \"{}\". Reply with only the translation: \"{}\"",
+                target, text
+            ))
+            .await?;
+
+        Ok(response.message_choices[0]
+            .message
+            .content
+            .trim()
+            .trim_matches('"')
+            .to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct HttpTranslation {
+    #[serde(rename = "translatedText")]
+    translated_text: Option<String>,
+    error: Option<String>,
+}
+
+/// Posts to a LibreTranslate compatible `/translate` endpoint, e.g. one running locally
+pub struct HttpTranslator {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl HttpTranslator {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            // Only fails when the TLS backend can't be initialized, like `Client::new`
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to build the HTTP client"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Translator for HttpTranslator {
+    async fn translate(&self, text: &str, target: &str) -> SourceCmdGuiResult<String> {
+        let response: HttpTranslation = self
+            .client
+            .post(&self.endpoint)
+            .json(&json!({
+                "q": text,
+                "source": "auto",
+                "target": target,
+                "format": "text",
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        match response {
+            HttpTranslation {
+                translated_text: Some(translated),
+                ..
+            } => Ok(translated),
+            HttpTranslation { error, .. } => Err(SourceCmdGuiError::TranslationError(
+                error.unwrap_or_else(|| "The endpoint returned no translation".to_string()),
+            )),
+        }
+    }
+}
+
+/// Builds the translator for the configured backend
+///
+/// # Arguments
+/// config - The translation config
+/// chat_gpt - The ChatGPT client, if an API key is stored
+///
+/// # Returns
+/// `None` if the backend is ChatGPT and there is no client
+pub fn build(config: &TranslationConfig, chat_gpt: Option<ChatGPT>) -> Option<Box<dyn Translator>> {
+    match config.backend {
+        TranslationBackend::ChatGpt => {
+            chat_gpt.map(|client| Box::new(ChatGptTranslator { client }) as Box<dyn Translator>)
+        }
+        TranslationBackend::Http => Some(Box::new(HttpTranslator::new(&config.endpoint))),
+    }
+}
+
+/// The ISO 639-1 code of the language a message is written in
+///
+/// # Returns
+/// `None` when the message is too short or mixed to tell reliably
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let info = whatlang::detect(text)?;
+
+    // Other scripts narrow it down to a few languages, so their guesses are good enough
+    if info.script() == Script::Latin && info.confidence() < MIN_CONFIDENCE {
+        return None;
+    }
+
+    LANGUAGES
+        .iter()
+        .find(|(lang, _)| *lang == info.lang())
+        .map(|(_, code)| *code)
+}
+
+pub fn is_language_code(code: &str) -> bool {
+    LANGUAGES.iter().any(|(_, known)| *known == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("Hola amigos, ¿quieren jugar otra partida conmigo esta noche?"),
+            Some("es")
+        );
+        assert_eq!(
+            detect_language("Does anyone want to play another round with me tonight?"),
+            Some("en")
+        );
+        assert_eq!(detect_language("Привет всем, как дела?"), Some("ru"));
+        assert_eq!(detect_language("lol nice shot"), None);
+        assert_eq!(detect_language("gg"), None);
+    }
+
+    /// Answers a single request with `body`, returning the request it received
+    async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/translate", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+
+            // Read until the JSON body is complete or the client hangs up
+            while !request.ends_with(b"}") {
+                let read = stream.read(&mut buffer).await.unwrap();
+
+                if read == 0 {
+                    break;
+                }
+
+                request.extend_from_slice(&buffer[..read]);
+            }
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+
+            String::from_utf8(request).unwrap()
+        });
+
+        (endpoint, server)
+    }
+
+    #[tokio::test]
+    async fn test_http_translator() {
+        let (endpoint, server) = serve_once(r#"{"translatedText": "good game"}"#).await;
+        let translator = HttpTranslator::new(&endpoint);
+
+        assert_eq!(
+            translator.translate("gut gespielt", "en").await.unwrap(),
+            "good game"
+        );
+
+        let request = server.await.unwrap();
+
+        assert!(request.starts_with("POST /translate"));
+        assert!(request.contains(r#""q":"gut gespielt""#));
+        assert!(request.contains(r#""target":"en""#));
+    }
+
+    #[tokio::test]
+    async fn test_http_translator_error() {
+        let (endpoint, _server) = serve_once(r#"{"error": "xx is not supported"}"#).await;
+        let translator = HttpTranslator::new(&endpoint);
+
+        assert!(matches!(
+            translator.translate("hallo", "xx").await,
+            Err(SourceCmdGuiError::TranslationError(message)) if message == "xx is not supported"
+        ));
+    }
+}
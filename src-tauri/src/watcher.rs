@@ -0,0 +1,328 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Restart delays double from `MIN_BACKOFF` up to `MAX_BACKOFF`
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A run lasting this long resets the backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// What changed about the tailed log file between two polls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFileEvent {
+    /// The file shrank, e.g. the game reopened it with truncation
+    Truncated,
+    /// A different file now lives at the path, e.g. the old one was renamed away
+    Rotated,
+    Removed,
+    /// The file appeared again after being removed
+    Created,
+}
+
+impl LogFileEvent {
+    /// Whether the parser has to reopen the file to keep seeing new lines
+    pub fn requires_restart(&self) -> bool {
+        !matches!(self, LogFileEvent::Removed)
+    }
+}
+
+impl fmt::Display for LogFileEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let event = match self {
+            LogFileEvent::Truncated => "truncated",
+            LogFileEvent::Rotated => "rotated",
+            LogFileEvent::Removed => "removed",
+            LogFileEvent::Created => "created",
+        };
+
+        write!(f, "{}", event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileState {
+    len: u64,
+    /// Identifies the file itself rather than its path (the inode on unix)
+    id: u64,
+}
+
+impl FileState {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+
+        Some(Self {
+            len: metadata.len(),
+            id: file_id(&metadata),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .created()
+        .ok()
+        .and_then(|created| created.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |created| created.as_nanos() as u64)
+}
+
+pub fn detect_change(
+    previous: Option<FileState>,
+    current: Option<FileState>,
+) -> Option<LogFileEvent> {
+    match (previous, current) {
+        (Some(_), None) => Some(LogFileEvent::Removed),
+        (None, Some(_)) => Some(LogFileEvent::Created),
+        (Some(previous), Some(current)) if previous.id != current.id => Some(LogFileEvent::Rotated),
+        (Some(previous), Some(current)) if current.len < previous.len => {
+            Some(LogFileEvent::Truncated)
+        }
+        _ => None,
+    }
+}
+
+/// Polls the tailed log for truncation, rotation and re-creation
+pub struct LogWatcher {
+    path: PathBuf,
+    state: Option<FileState>,
+}
+
+impl LogWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let state = FileState::read(&path);
+
+        Self { path, state }
+    }
+
+    pub fn poll(&mut self) -> Option<LogFileEvent> {
+        let current = FileState::read(&self.path);
+        let event = detect_change(self.state, current);
+
+        self.state = current;
+
+        event
+    }
+}
+
+/// Reads the lines appended to the log since the last read
+pub struct LogTailer {
+    path: PathBuf,
+    offset: u64,
+    /// The start of a line that hasn't been terminated yet
+    partial: String,
+}
+
+impl LogTailer {
+    /// Starts at the end of the file, so only lines written from now on are read
+    pub fn new(path: PathBuf) -> Self {
+        let offset = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+
+        Self {
+            path,
+            offset,
+            partial: String::new(),
+        }
+    }
+
+    /// Starts at the beginning of the file, for a log that was just rotated or truncated so
+    /// lines written before the tailer was created aren't skipped
+    pub fn from_start(path: PathBuf) -> Self {
+        Self {
+            path,
+            offset: 0,
+            partial: String::new(),
+        }
+    }
+
+    pub fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        // Truncated, start over from the beginning
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+
+        let mut buffer = Vec::new();
+        file.seek(SeekFrom::Start(self.offset))?;
+        self.offset += file.read_to_end(&mut buffer)? as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&buffer));
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return Ok(vec![]);
+        };
+
+        let lines = self.partial[..end]
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+
+        self.partial.drain(..=end);
+
+        Ok(lines)
+    }
+}
+
+/// Exponential backoff between automatic restarts
+#[derive(Default)]
+pub struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    /// # Arguments
+    /// run_time - How long the failed run lasted
+    ///
+    /// # Returns
+    /// How long to wait before the next restart
+    pub fn next_delay(&mut self, run_time: Duration) -> Duration {
+        if run_time >= STABLE_RUN {
+            self.attempt = 0;
+        }
+
+        let delay = MIN_BACKOFF
+            .saturating_mul(2u32.saturating_pow(self.attempt))
+            .min(MAX_BACKOFF);
+
+        self.attempt = self.attempt.saturating_add(1);
+
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(len: u64, id: u64) -> Option<FileState> {
+        Some(FileState { len, id })
+    }
+
+    #[test]
+    fn test_detect_change() {
+        assert_eq!(detect_change(state(10, 1), state(20, 1)), None);
+        assert_eq!(detect_change(None, None), None);
+        assert_eq!(
+            detect_change(state(20, 1), state(5, 1)),
+            Some(LogFileEvent::Truncated)
+        );
+        assert_eq!(
+            detect_change(state(20, 1), state(30, 2)),
+            Some(LogFileEvent::Rotated)
+        );
+        assert_eq!(
+            detect_change(state(20, 1), None),
+            Some(LogFileEvent::Removed)
+        );
+        assert_eq!(
+            detect_change(None, state(0, 2)),
+            Some(LogFileEvent::Created)
+        );
+    }
+
+    #[test]
+    fn test_watcher_detects_truncation_and_recreation() {
+        let path = std::env::temp_dir().join(format!("{}.log", uuid::Uuid::new_v4()));
+        fs::write(&path, "line\nline\n").unwrap();
+
+        let mut watcher = LogWatcher::new(path.clone());
+        assert_eq!(watcher.poll(), None);
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(watcher.poll(), Some(LogFileEvent::Truncated));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll(), Some(LogFileEvent::Removed));
+
+        fs::write(&path, "line\n").unwrap();
+        assert_eq!(watcher.poll(), Some(LogFileEvent::Created));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_tailer_reads_appended_lines() {
+        let path = std::env::temp_dir().join(format!("{}.log", uuid::Uuid::new_v4()));
+        fs::write(&path, "old line\n").unwrap();
+
+        let mut tailer = LogTailer::new(path.clone());
+        assert!(tailer.read_lines().unwrap().is_empty());
+
+        fs::write(&path, "old line\nfirst\nsecond\nhalf").unwrap();
+        assert_eq!(tailer.read_lines().unwrap(), vec!["first", "second"]);
+
+        fs::write(&path, "old line\nfirst\nsecond\nhalf a line\n").unwrap();
+        assert_eq!(tailer.read_lines().unwrap(), vec!["half a line"]);
+
+        fs::write(&path, "new\n").unwrap();
+        assert_eq!(tailer.read_lines().unwrap(), vec!["new"]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_no_lines_are_lost_across_a_rotation() {
+        let path = std::env::temp_dir().join(format!("{}.log", uuid::Uuid::new_v4()));
+        let rotated = path.with_extension("log.1");
+        let append = |line: &str| {
+            let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+            io::Write::write_all(&mut file, line.as_bytes()).unwrap();
+        };
+
+        fs::write(&path, "old line\n").unwrap();
+
+        let mut watcher = LogWatcher::new(path.clone());
+        let mut tailer = LogTailer::new(path.clone());
+
+        append("before\n");
+        assert_eq!(tailer.read_lines().unwrap(), vec!["before"]);
+
+        fs::rename(&path, &rotated).unwrap();
+        fs::write(&path, "first\n").unwrap();
+        assert_eq!(watcher.poll(), Some(LogFileEvent::Rotated));
+
+        // Written while the parser is being rebuilt
+        append("second\n");
+
+        let mut tailer = LogTailer::from_start(path.clone());
+
+        append("third\n");
+        assert_eq!(
+            tailer.read_lines().unwrap(),
+            vec!["first", "second", "third"]
+        );
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(rotated).unwrap();
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::default();
+
+        let delays: Vec<u64> = (0..8)
+            .map(|_| backoff.next_delay(Duration::ZERO).as_secs())
+            .collect();
+
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff.next_delay(STABLE_RUN).as_secs(), 1);
+    }
+}
@@ -1,16 +1,25 @@
 import { Component, ElementRef, OnInit, ViewChild } from "@angular/core";
 import { invoke } from "@tauri-apps/api/tauri";
 import { FormsModule } from '@angular/forms';
-import { Log, StdService } from "./std.service";
+import { Log, ParserStatus, QueuedMessage, StdService } from "./std.service";
 
 interface Config {
+    version: number,
     file_path: string,
     command_timeout: number,
     owner: String,
+    owner_steam_id: String,
     parser: GameParser,
-    openai_api_key: string,
+    custom_parser: CustomParserConfig,
+    output: OutputConfig,
+    moderation: ModerationConfig,
+    translation: TranslationConfig,
+    eval: EvalConfig,
+    poll: PollConfig,
+    openai_api_key_secret: string,
     disabled_commands: string[],
     response_direction: string,
+    auto_restart: boolean,
 }
 
 enum GameParser {
@@ -18,6 +27,139 @@ enum GameParser {
     CounterStrike2 = "Counter Strike 2",
     CounterStrikeSource = "Counter Strike Source",
     Minecraft = "Minecraft",
+    TeamFortress2 = "Team Fortress 2",
+    GarrysMod = "Garry's Mod",
+    Left4Dead2 = "Left 4 Dead 2",
+    HalfLife2Deathmatch = "Half-Life 2: Deathmatch",
+    CounterStrike16 = "Counter-Strike 1.6",
+    Custom = "Custom",
+}
+
+interface CustomParserConfig {
+    pattern: string,
+    chat_key: string,
+    max_chat_length: number,
+}
+
+enum OutputKind {
+    Keystrokes = "Keystrokes",
+    ExecCfg = "Exec Cfg",
+    Rcon = "RCON",
+    MinecraftRcon = "Minecraft RCON",
+    Capture = "Capture",
+}
+
+enum MinecraftReply {
+    Tellraw = "Tellraw",
+    Say = "Say",
+    PrivateMessage = "Private Message",
+}
+
+interface OutputConfig {
+    kind: OutputKind,
+    exec_cfg_path: string,
+    exec_key: string,
+    rcon_address: string,
+    rcon_password_secret: string,
+    minecraft_reply: MinecraftReply,
+}
+
+enum ModerationAction {
+    Log = "Log",
+    Warn = "Warn",
+    Kick = "Kick",
+    Mute = "Mute",
+}
+
+interface ModerationConfig {
+    banned_words: string[],
+    spam_window: number,
+    max_messages: number,
+    max_repeats: number,
+    max_caps_percent: number,
+    min_caps_length: number,
+    action: ModerationAction,
+    warning: string,
+    kick_command: string,
+    mute_command: string,
+}
+
+enum TranslationBackend {
+    ChatGpt = "ChatGPT",
+    Http = "HTTP",
+}
+
+interface TranslationConfig {
+    backend: TranslationBackend,
+    endpoint: string,
+    auto_translate: boolean,
+    target_language: string,
+}
+
+interface EvalConfig {
+    precision: number,
+    // How much of each currency one US dollar buys
+    currencies: { [code: string]: number },
+    // How readily lines that only might be math are answered, from 0 to 100
+    sensitivity: number,
+    explicit_only: boolean,
+}
+
+interface PollConfig {
+    // Seconds a poll collects votes
+    duration: number,
+}
+
+interface ModerationHit {
+    time: string,
+    player: string,
+    message: string,
+    reason: string,
+    action: ModerationAction,
+}
+
+interface PatternMatch {
+    line: string,
+    captured?: {
+        user: string,
+        message: string,
+        channel?: string,
+        team?: string,
+        dead: boolean,
+    },
+}
+
+interface ConfigFieldError {
+    field: string,
+    message: string,
+}
+
+interface InvalidConfigError {
+    kind: 'InvalidConfig',
+    message: string,
+    fields: ConfigFieldError[],
+}
+
+interface LogCandidate {
+    parser: GameParser,
+    path: string,
+    exists: boolean,
+    active: boolean,
+    hint?: string,
+}
+
+interface ConfigUpdate {
+    restart_required: string[],
+}
+
+interface PlayerStats {
+    name: string,
+    messages: number,
+    commands: { [command: string]: number },
+    kills: number,
+    deaths: number,
+    first_seen: string,
+    last_seen: string,
 }
 
 interface Command {
@@ -34,23 +176,87 @@ interface Command {
 })
 export class AppComponent implements OnInit {
     config: Config = {
+        version: 0,
         file_path: '',
         command_timeout: 0,
         owner: '',
+        owner_steam_id: '',
         parser: GameParser.CounterStrike2,
-        openai_api_key: '',
+        custom_parser: {
+            pattern: '',
+            chat_key: 'y',
+            max_chat_length: 127,
+        },
+        output: {
+            kind: OutputKind.Keystrokes,
+            exec_cfg_path: '',
+            exec_key: '',
+            rcon_address: '127.0.0.1:27015',
+            rcon_password_secret: 'rcon_password',
+            minecraft_reply: MinecraftReply.Tellraw,
+        },
+        moderation: {
+            banned_words: [],
+            spam_window: 10,
+            max_messages: 6,
+            max_repeats: 3,
+            max_caps_percent: 70,
+            min_caps_length: 8,
+            action: ModerationAction.Log,
+            warning: '{player}, please stop {reason}',
+            kick_command: 'kick "{player}"',
+            mute_command: 'sm_mute "{player}"',
+        },
+        translation: {
+            backend: TranslationBackend.ChatGpt,
+            endpoint: 'http://localhost:5000/translate',
+            auto_translate: false,
+            target_language: 'en',
+        },
+        eval: {
+            precision: 4,
+            currencies: {},
+            sensitivity: 50,
+            explicit_only: false,
+        },
+        poll: {
+            duration: 60,
+        },
+        openai_api_key_secret: 'openai_api_key',
         disabled_commands: [],
         response_direction: '',
+        auto_restart: false,
     };
     
 
+    // Write-only, the stored key is never sent back to the frontend
+    openaiApiKey: string = '';
+    hasOpenaiApiKey: boolean = false;
+    rconPassword: string = '';
+    hasRconPassword: boolean = false;
+
+    configErrors: ConfigFieldError[] = [];
+    logCandidates: LogCandidate[] = [];
+    // Fields only applied when the parser starts, and the ones changed since it did
+    restartFields: string[] = [];
+    pendingRestartFields: string[] = [];
+
+    // Sample log lines to try the custom parser pattern on
+    patternSample: string = '';
+    patternMatches: PatternMatch[] = [];
+    patternError: string | null = null;
+
     commands: Command[] = [];
+    playerStats: PlayerStats[] = [];
+    moderationLog: ModerationHit[] = [];
     stdoutMessages: Log[] = [];
+    outgoingQueue: QueuedMessage[] = [];
     @ViewChild('logContainer') private logContainer!: ElementRef;
 
     isRunning: boolean = false;
     stopping: boolean = false;
-    activeTab: 'settings' | 'logs' | 'python-scripts' = 'settings';
+    lastError: string | null = null;
+    activeTab: 'settings' | 'logs' | 'python-scripts' | 'stats' | 'moderation' = 'settings';
 
     constructor(private stdService: StdService) {
     }
@@ -62,6 +268,14 @@ export class AppComponent implements OnInit {
             invoke("get_config").then((res) => {
                 this.config = res as Config;
 
+                invoke("has_secret", { name: this.config.openai_api_key_secret }).then((res) => {
+                    this.hasOpenaiApiKey = res as boolean;
+                });
+
+                invoke("has_secret", { name: this.config.output.rcon_password_secret }).then((res) => {
+                    this.hasRconPassword = res as boolean;
+                });
+
                 this.commands.forEach((command) => {
                     command.enabled = !this.config.disabled_commands?.includes(command.id);
                 });
@@ -74,6 +288,35 @@ export class AppComponent implements OnInit {
             this.isRunning = res as boolean;
         });
 
+        invoke("get_last_error").then((res) => {
+            this.lastError = res as string | null;
+        });
+
+        this.stdService.parserStatus$.subscribe((status) => {
+            if (status === null) {
+                return;
+            }
+
+            this.isRunning = ['Starting', 'Running', 'Stopping', 'Restarting'].includes(status.state);
+
+            if (status.state === 'Crashed') {
+                this.lastError = status.error ?? null;
+                this.stopping = false;
+            }
+        });
+
+        invoke("get_restart_required_fields").then((res) => {
+            this.restartFields = res as string[];
+        });
+
+        invoke("get_outgoing_queue").then((res) => {
+            this.outgoingQueue = res as QueuedMessage[];
+        });
+
+        this.stdService.outgoingQueue$.subscribe((queue) => {
+            this.outgoingQueue = queue;
+        });
+
 
         this.stdService.stdoutData$.subscribe((data) => {
             if (data.message === '') {
@@ -116,11 +359,28 @@ export class AppComponent implements OnInit {
 
                 invoke("stop").then((res) => {
                     this.isRunning = false;
+                    this.pendingRestartFields = [];
+                }).catch((err) => {
+                    this.lastError = err as string;
+                }).finally(() => {
                     this.stopping = false;
                 });
             } else {
                 invoke("start", { config: this.config }).then((res) => {
                     this.isRunning = true;
+                    this.lastError = null;
+                    this.configErrors = [];
+                    this.pendingRestartFields = [];
+                }).catch((err) => {
+                    const error = err as string | InvalidConfigError;
+
+                    if (typeof error === 'string') {
+                        this.lastError = error;
+                        return;
+                    }
+
+                    this.configErrors = error.fields;
+                    this.changeTab('settings');
                 });
             }
         });
@@ -154,15 +414,139 @@ export class AppComponent implements OnInit {
         return this.activeTab === tab;
     }
 
-    changeTab(logs: 'settings' | 'logs' | 'python-scripts'): void {
+    changeTab(logs: 'settings' | 'logs' | 'python-scripts' | 'stats' | 'moderation'): void {
         this.activeTab = logs;
         this.scrollToBottom();
+
+        if (logs === 'stats') {
+            this.loadPlayerStats();
+        }
+
+        if (logs === 'moderation') {
+            this.loadModerationLog();
+        }
+    }
+
+    clearOutgoingQueue(): void {
+        invoke("clear_outgoing_queue");
+    }
+
+    loadPlayerStats(): void {
+        invoke("get_player_stats").then((res) => {
+            this.playerStats = res as PlayerStats[];
+        });
+    }
+
+    loadModerationLog(): void {
+        invoke("get_moderation_log").then((res) => {
+            this.moderationLog = res as ModerationHit[];
+        });
+    }
+
+    clearModerationLog(): void {
+        invoke("clear_moderation_log").then(() => {
+            this.moderationLog = [];
+        });
+    }
+
+    // One banned word or phrase per line
+    get bannedWords(): string {
+        return this.config.moderation.banned_words.join('\n');
+    }
+
+    updateBannedWords(text: string): void {
+        this.config.moderation.banned_words = text
+            .split('\n')
+            .map((word) => word.trim())
+            .filter((word) => word.length > 0);
+        this.updateConfig();
+    }
+
+    // One "code rate" pair per line
+    get currencyRates(): string {
+        return Object.entries(this.config.eval.currencies)
+            .map(([code, rate]) => `${code} ${rate}`)
+            .join('\n');
+    }
+
+    updateCurrencyRates(text: string): void {
+        const currencies: { [code: string]: number } = {};
+
+        for (const line of text.split('\n')) {
+            const [code, rate] = line.trim().split(/\s+/);
+
+            if (code && rate) {
+                currencies[code.toLowerCase()] = Number(rate);
+            }
+        }
+
+        this.config.eval.currencies = currencies;
+        this.updateConfig();
+    }
+
+    commandUses(stats: PlayerStats): number {
+        return Object.values(stats.commands).reduce((total, uses) => total + uses, 0);
     }
 
     updateConfig(): void {
         invoke("save_config", { config: this.config }).then((res) => {
+            this.pendingRestartFields = (res as ConfigUpdate).restart_required;
             console.log('updated config');
         });
+
+        if (this.configErrors.length > 0) {
+            invoke("validate_config", { config: this.config }).then((res) => {
+                this.configErrors = res as ConfigFieldError[];
+            });
+        }
+    }
+
+    discoverLogFiles(): void {
+        invoke("discover_log_files").then((res) => {
+            this.logCandidates = res as LogCandidate[];
+        });
+    }
+
+    useLogCandidate(candidate: LogCandidate): void {
+        this.config.file_path = candidate.path;
+        this.config.parser = candidate.parser;
+        this.logCandidates = [];
+        this.updateConfig();
+    }
+
+    testParserPattern(): void {
+        const lines = this.patternSample.split('\n').filter((line) => line.trim() !== '');
+
+        invoke("test_parser_pattern", { pattern: this.config.custom_parser.pattern, lines: lines }).then((res) => {
+            this.patternMatches = res as PatternMatch[];
+            this.patternError = null;
+        }).catch((err) => {
+            this.patternMatches = [];
+            this.patternError = err as string;
+        });
+    }
+
+    requiresRestart(field: string): boolean {
+        return this.isRunning && this.restartFields.includes(field);
+    }
+
+    fieldError(field: string): string | undefined {
+        return this.configErrors.find((error) => error.field === field)?.message;
+    }
+
+    updateOpenaiApiKey(): void {
+        invoke("set_secret", { name: this.config.openai_api_key_secret, value: this.openaiApiKey }).then(() => {
+            this.hasOpenaiApiKey = this.openaiApiKey !== '';
+            this.openaiApiKey = '';
+        });
+    }
+
+    updateRconPassword(): void {
+        invoke("set_secret", { name: this.config.output.rcon_password_secret, value: this.rconPassword }).then(() => {
+            this.hasRconPassword = this.rconPassword !== '';
+            this.rconPassword = '';
+            this.updateConfig();
+        });
     }
 
     updateCommandState(command: Command): void {
@@ -10,6 +10,20 @@ export interface Log {
     message: string,
 }
 
+export interface ParserStatus {
+    state: 'Stopped' | 'Starting' | 'Running' | 'Stopping' | 'Restarting' | 'Crashed',
+    error?: string,
+}
+
+export interface QueuedMessage {
+    id: number,
+    message: string,
+    player?: string,
+    whisper: boolean,
+    priority: 'Low' | 'Normal' | 'High',
+    queued_at: string,
+}
+
 @Injectable({
     providedIn: 'root'
 })
@@ -22,11 +36,29 @@ export class StdService {
     });
     stdoutData$ = this.stdoutData.asObservable();
 
+    private parserStatus = new BehaviorSubject<ParserStatus | null>(null);
+    parserStatus$ = this.parserStatus.asObservable();
+
+    private outgoingQueue = new BehaviorSubject<QueuedMessage[]>([]);
+    outgoingQueue$ = this.outgoingQueue.asObservable();
+
     constructor(private zone: NgZone) {
         appWindow.listen('stdout_data', (event) => {
             this.zone.run(() => {
                 this.stdoutData.next(event.payload as unknown as Log);
             });
         });
+
+        appWindow.listen('parser_status', (event) => {
+            this.zone.run(() => {
+                this.parserStatus.next(event.payload as ParserStatus);
+            });
+        });
+
+        appWindow.listen('outgoing_queue', (event) => {
+            this.zone.run(() => {
+                this.outgoingQueue.next(event.payload as QueuedMessage[]);
+            });
+        });
     }
 }